@@ -2,6 +2,16 @@ use rust_embed::RustEmbed;
 
 pub const COMMIT: &str = include_str!("../assets/commit");
 
+#[derive(RustEmbed)]
+#[folder = "config"]
+pub struct AssetsConfig;
+
+/// 内置的种子数据，包含已知的密码，只在调试构建中内置，发布构建只读取外部文件
+#[cfg(debug_assertions)]
+#[derive(RustEmbed)]
+#[folder = "seeds"]
+pub struct AssetsSeeds;
+
 #[derive(RustEmbed)]
 #[folder = "assets/openapi"]
 pub struct AssetsOpenapi;
@@ -0,0 +1,377 @@
+use std::fmt::{Debug, Formatter};
+use std::time::Duration;
+
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::configs::ConfigErrors;
+use crate::core::errors::{AppError, AppResult};
+use crate::core::secret;
+
+/// HS256密钥的最小长度
+const MIN_SECRET_LEN: usize = 32;
+
+/// 刷新令牌的`typ`，只能用于换取新的令牌
+pub const REFRESH_TOKEN_TYPE: &str = "refresh";
+
+/// JWT认证配置，修改后需要重启生效
+#[serde_with::serde_as]
+#[derive(Clone, Deserialize, PartialEq)]
+pub struct Jwt {
+    /// 签名算法：`HS256`(默认)/`RS256`
+    #[serde(default)]
+    pub algorithm: JwtAlgorithm,
+    /// `HS256`的密钥，至少32位
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// `RS256`验证签名的公钥（PEM）
+    #[serde(default)]
+    pub public_key: Option<String>,
+    /// `RS256`签发令牌的私钥（PEM），不配置时不能登录，只验证其他系统签发的令牌
+    #[serde(default)]
+    pub private_key: Option<String>,
+    /// 配置后校验`iss`且必须存在
+    #[serde(default)]
+    pub issuer: Option<String>,
+    /// 配置后校验`aud`且必须存在，满足其一即可
+    #[serde(default)]
+    pub audience: Vec<String>,
+    /// 校验`exp`、`nbf`时允许的时钟偏差，默认60秒
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_leeway")]
+    pub leeway: Duration,
+    /// 登录签发的访问令牌有效期，默认15分钟
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_access_ttl")]
+    pub access_ttl: Duration,
+    /// 登录签发的刷新令牌有效期，默认7天
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_refresh_ttl")]
+    pub refresh_ttl: Duration,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub enum JwtAlgorithm {
+    #[default]
+    HS256,
+    RS256,
+}
+
+/// 输出时隐藏密钥
+impl Debug for Jwt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Jwt")
+            .field("algorithm", &self.algorithm)
+            .field("secret", &self.secret.as_ref().map(|_| secret::REDACTED))
+            .field("public_key", &self.public_key)
+            .field("private_key", &self.private_key.as_ref().map(|_| secret::REDACTED))
+            .field("issuer", &self.issuer)
+            .field("audience", &self.audience)
+            .field("leeway", &self.leeway)
+            .field("access_ttl", &self.access_ttl)
+            .field("refresh_ttl", &self.refresh_ttl)
+            .finish()
+    }
+}
+
+impl Jwt {
+    pub(crate) fn validate(&self, errors: &mut ConfigErrors) {
+        match self.algorithm {
+            JwtAlgorithm::HS256 => match &self.secret {
+                Some(s) if s.len() >= MIN_SECRET_LEN => {}
+                Some(_) => errors.push("jwt.secret", format!("must be at least {} characters", MIN_SECRET_LEN)),
+                None => errors.push("jwt.secret", "is required by HS256"),
+            },
+            JwtAlgorithm::RS256 => match &self.public_key {
+                Some(key) => {
+                    if let Err(e) = DecodingKey::from_rsa_pem(key.as_bytes()) {
+                        errors.push("jwt.public_key", format!("is not a valid RSA public key: {}", e));
+                    }
+                }
+                None => errors.push("jwt.public_key", "is required by RS256"),
+            },
+        }
+        if let Some(key) = &self.private_key {
+            if let Err(e) = EncodingKey::from_rsa_pem(key.as_bytes()) {
+                errors.push("jwt.private_key", format!("is not a valid RSA private key: {}", e));
+            }
+        }
+        if self.access_ttl.is_zero() || self.refresh_ttl <= self.access_ttl {
+            errors.push("jwt.refresh_ttl", "must be longer than a non-zero access_ttl");
+        }
+        if self.issuer.as_deref().is_some_and(|i| i.trim().is_empty()) {
+            errors.push("jwt.issuer", "must not be empty");
+        }
+    }
+}
+
+fn default_leeway() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_access_ttl() -> Duration {
+    Duration::from_secs(15 * 60)
+}
+
+fn default_refresh_ttl() -> Duration {
+    Duration::from_secs(7 * 24 * 60 * 60)
+}
+
+/// 令牌中的声明，`iss`、`aud`等标准声明已在验证时校验
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Claims {
+    /// 用户标识
+    pub sub: String,
+    /// 用户名，日志中优先使用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 以空格分隔的权限范围
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// 令牌类型，[`REFRESH_TOKEN_TYPE`]表示刷新令牌
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typ: Option<String>,
+    /// 签发时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<u64>,
+    /// 令牌ID，刷新令牌通过它保证只能使用一次
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    pub exp: u64,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// 按配置验证令牌
+pub struct JwtVerifier {
+    key: DecodingKey,
+    validation: Validation,
+}
+
+impl JwtVerifier {
+    /// 配置需要先通过[`Jwt::validate`]校验
+    pub fn new(config: &Jwt) -> AppResult<Self> {
+        let (algorithm, key) = match config.algorithm {
+            JwtAlgorithm::HS256 => {
+                let secret = config.secret.as_deref().ok_or(jsonwebtoken::errors::Error::from(ErrorKind::InvalidKeyFormat))?;
+                (Algorithm::HS256, DecodingKey::from_secret(secret.as_bytes()))
+            }
+            JwtAlgorithm::RS256 => {
+                let key = config.public_key.as_deref().ok_or(jsonwebtoken::errors::Error::from(ErrorKind::InvalidKeyFormat))?;
+                (Algorithm::RS256, DecodingKey::from_rsa_pem(key.as_bytes())?)
+            }
+        };
+        let mut validation = Validation::new(algorithm);
+        validation.leeway = config.leeway.as_secs();
+        validation.validate_nbf = true;
+        let mut required = vec!["exp", "sub"];
+        if let Some(issuer) = &config.issuer {
+            validation.set_issuer(&[issuer]);
+            required.push("iss");
+        }
+        if config.audience.is_empty() {
+            validation.validate_aud = false;
+        } else {
+            validation.set_audience(&config.audience);
+            required.push("aud");
+        }
+        validation.set_required_spec_claims(&required);
+        Ok(Self { key, validation })
+    }
+
+    /// 验证签名及`exp`/`nbf`/`iss`/`aud`，不接受刷新令牌，失败时返回401
+    pub fn verify(&self, token: &str) -> AppResult<Claims> {
+        let claims = self.decode(token)?;
+        if claims.typ.as_deref() == Some(REFRESH_TOKEN_TYPE) {
+            return Err(AppError::Unauthorized("invalid token type"));
+        }
+        Ok(claims)
+    }
+
+    /// 只接受刷新令牌
+    pub fn verify_refresh(&self, token: &str) -> AppResult<Claims> {
+        let claims = self.decode(token)?;
+        if claims.typ.as_deref() != Some(REFRESH_TOKEN_TYPE) {
+            return Err(AppError::Unauthorized("invalid token type"));
+        }
+        Ok(claims)
+    }
+
+    fn decode(&self, token: &str) -> AppResult<Claims> {
+        let data = jsonwebtoken::decode::<Claims>(token, &self.key, &self.validation).map_err(|e| {
+            tracing::debug!("invalid jwt: {}", e);
+            AppError::Unauthorized(match e.kind() {
+                ErrorKind::ExpiredSignature => "token expired",
+                ErrorKind::ImmatureSignature => "token not yet valid",
+                ErrorKind::InvalidIssuer => "invalid token issuer",
+                ErrorKind::InvalidAudience => "invalid token audience",
+                ErrorKind::MissingRequiredClaim(_) => "missing required claim",
+                _ => "invalid token",
+            })
+        })?;
+        Ok(data.claims)
+    }
+}
+
+/// 登录后签发令牌，签发的令牌可以通过[`JwtVerifier`]验证
+pub struct JwtIssuer {
+    key: EncodingKey,
+    header: Header,
+    issuer: Option<String>,
+    audience: Option<String>,
+    pub access_ttl: Duration,
+    pub refresh_ttl: Duration,
+}
+
+impl JwtIssuer {
+    /// `RS256`未配置私钥时返回空
+    pub fn new(config: &Jwt) -> AppResult<Option<Self>> {
+        let (algorithm, key) = match config.algorithm {
+            JwtAlgorithm::HS256 => match config.secret.as_deref() {
+                Some(secret) => (Algorithm::HS256, EncodingKey::from_secret(secret.as_bytes())),
+                None => return Ok(None),
+            },
+            JwtAlgorithm::RS256 => match config.private_key.as_deref() {
+                Some(key) => (Algorithm::RS256, EncodingKey::from_rsa_pem(key.as_bytes())?),
+                None => return Ok(None),
+            },
+        };
+        Ok(Some(Self {
+            key,
+            header: Header::new(algorithm),
+            issuer: config.issuer.clone(),
+            audience: config.audience.first().cloned(),
+            access_ttl: config.access_ttl,
+            refresh_ttl: config.refresh_ttl,
+        }))
+    }
+
+    pub fn access_token(&self, sub: &str, name: &str) -> AppResult<String> {
+        self.sign(sub, name, None, None, self.access_ttl)
+    }
+
+    /// `jti`需要由调用方保存，换取新令牌时校验
+    pub fn refresh_token(&self, sub: &str, name: &str, jti: &str) -> AppResult<String> {
+        self.sign(sub, name, Some(REFRESH_TOKEN_TYPE), Some(jti), self.refresh_ttl)
+    }
+
+    fn sign(&self, sub: &str, name: &str, typ: Option<&str>, jti: Option<&str>, ttl: Duration) -> AppResult<String> {
+        let now = jsonwebtoken::get_current_timestamp();
+        let mut extra = serde_json::Map::new();
+        if let Some(issuer) = &self.issuer {
+            extra.insert("iss".to_string(), issuer.clone().into());
+        }
+        if let Some(audience) = &self.audience {
+            extra.insert("aud".to_string(), audience.clone().into());
+        }
+        let claims = Claims {
+            sub: sub.to_string(),
+            name: Some(name.to_string()),
+            scope: None,
+            typ: typ.map(str::to_string),
+            iat: Some(now),
+            jti: jti.map(str::to_string),
+            exp: now + ttl.as_secs(),
+            extra,
+        };
+        Ok(jsonwebtoken::encode(&self.header, &claims, &self.key)?)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use jsonwebtoken::{EncodingKey, Header};
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    pub(crate) const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    pub(crate) fn hs256() -> Jwt {
+        Jwt {
+            algorithm: JwtAlgorithm::HS256,
+            secret: Some(SECRET.to_string()),
+            public_key: None,
+            private_key: None,
+            issuer: Some("https://auth.example.com".to_string()),
+            audience: vec!["application".to_string()],
+            leeway: Duration::from_secs(0),
+            access_ttl: default_access_ttl(),
+            refresh_ttl: default_refresh_ttl(),
+        }
+    }
+
+    /// 以当前时间为基准的声明
+    pub(crate) fn claims(sub: &str, exp_in: i64) -> Value {
+        let now = jsonwebtoken::get_current_timestamp() as i64;
+        json!({"sub": sub, "name": "tester", "iss": "https://auth.example.com", "aud": "application", "exp": now + exp_in})
+    }
+
+    pub(crate) fn sign(claims: &Value) -> String {
+        jsonwebtoken::encode(&Header::default(), claims, &EncodingKey::from_secret(SECRET.as_bytes())).unwrap()
+    }
+
+    fn error(verifier: &JwtVerifier, token: &str) -> String {
+        verifier.verify(token).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_verify() {
+        let verifier = JwtVerifier::new(&hs256()).unwrap();
+        let verified = verifier.verify(&sign(&claims("1", 60))).unwrap();
+        assert_eq!(verified.sub, "1");
+        assert_eq!(verified.name.as_deref(), Some("tester"));
+        assert_eq!(verified.extra["iss"], "https://auth.example.com");
+
+        assert_eq!(error(&verifier, &sign(&claims("1", -10))), "token expired");
+        let mut nbf = claims("1", 60);
+        nbf["nbf"] = json!(jsonwebtoken::get_current_timestamp() + 30);
+        assert_eq!(error(&verifier, &sign(&nbf)), "token not yet valid");
+        let mut iss = claims("1", 60);
+        iss["iss"] = json!("https://other.example.com");
+        assert_eq!(error(&verifier, &sign(&iss)), "invalid token issuer");
+        let mut aud = claims("1", 60);
+        aud["aud"] = json!(["other", "another"]);
+        assert_eq!(error(&verifier, &sign(&aud)), "invalid token audience");
+        let mut missing = claims("1", 60);
+        missing.as_object_mut().unwrap().remove("aud");
+        assert_eq!(error(&verifier, &sign(&missing)), "missing required claim");
+
+        let other = jsonwebtoken::encode(&Header::default(), &claims("1", 60), &EncodingKey::from_secret(b"another secret")).unwrap();
+        assert_eq!(error(&verifier, &other), "invalid token");
+        assert_eq!(error(&verifier, "not.a.token"), "invalid token");
+    }
+
+    #[test]
+    fn test_rs256() {
+        let config = Jwt {
+            algorithm: JwtAlgorithm::RS256,
+            secret: None,
+            public_key: Some(include_str!("testdata/rsa_public.pem").to_string()),
+            private_key: Some(include_str!("testdata/rsa_private.pem").to_string()),
+            ..hs256()
+        };
+        let mut errors = ConfigErrors::default();
+        config.validate(&mut errors);
+        assert!(errors.into_result().is_ok());
+
+        let verifier = JwtVerifier::new(&config).unwrap();
+        let key = EncodingKey::from_rsa_pem(include_bytes!("testdata/rsa_private.pem")).unwrap();
+        let token = jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims("2", 60), &key).unwrap();
+        assert_eq!(verifier.verify(&token).unwrap().sub, "2");
+        // 不接受其他算法签名的令牌
+        assert_eq!(error(&verifier, &sign(&claims("2", 60))), "invalid token");
+
+        // 访问令牌与刷新令牌不能混用
+        let issuer = JwtIssuer::new(&config).unwrap().unwrap();
+        let access = issuer.access_token("3", "carol").unwrap();
+        let refresh = issuer.refresh_token("3", "carol", "jti-1").unwrap();
+        assert_eq!(verifier.verify(&access).unwrap().name.as_deref(), Some("carol"));
+        let claims = verifier.verify_refresh(&refresh).unwrap();
+        assert_eq!((claims.sub.as_str(), claims.jti.as_deref()), ("3", Some("jti-1")));
+        assert_eq!(error(&verifier, &refresh), "invalid token type");
+        assert_eq!(verifier.verify_refresh(&access).unwrap_err().to_string(), "invalid token type");
+        assert!(JwtIssuer::new(&Jwt { private_key: None, ..config }).unwrap().is_none());
+    }
+}
@@ -0,0 +1,65 @@
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::auth::jwt::Claims;
+
+pub mod jwt;
+pub mod password;
+
+/// 查询用户，与迁移中初始化的权限一致
+pub const PERMISSION_USER_READ: &str = "user:read";
+/// 修改用户
+pub const PERMISSION_USER_WRITE: &str = "user:write";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrincipalKind {
+    /// 通过JWT认证的用户
+    User,
+    /// 通过`APP-TOKEN`认证的外部应用
+    App,
+}
+
+/// 已认证的调用方，认证通过后放入`Depot`
+#[derive(Clone, Debug)]
+pub struct Principal {
+    pub kind: PrincipalKind,
+    /// 用户标识（令牌中的`sub`）或密钥ID
+    pub id: String,
+    /// 日志中展示的用户名或应用名
+    pub name: String,
+    /// 权限范围
+    pub scopes: Vec<String>,
+}
+
+impl From<Claims> for Principal {
+    /// 令牌中没有`name`时使用`sub`，权限范围取自以空格分隔的`scope`
+    fn from(claims: Claims) -> Self {
+        Principal {
+            kind: PrincipalKind::User,
+            name: claims.name.unwrap_or_else(|| claims.sub.clone()),
+            id: claims.sub,
+            scopes: claims.scope.as_deref().map(split_scopes).unwrap_or_default(),
+        }
+    }
+}
+
+/// 以空格分隔的权限范围
+pub fn split_scopes(scopes: &str) -> Vec<String> {
+    scopes.split_whitespace().map(str::to_string).collect()
+}
+
+/// 64位十六进制的随机令牌
+pub fn random_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// 随机令牌使用SHA-256保存摘要即可
+pub fn digest(token: &str) -> String {
+    Sha256::digest(token.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 比较定长的摘要，耗时与内容、长度无关，避免通过响应时间猜测令牌或密码
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let (a, b) = (Sha256::digest(a), Sha256::digest(b));
+    a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
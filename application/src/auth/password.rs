@@ -0,0 +1,198 @@
+//! 密码使用Argon2id哈希，以PHC格式保存参数及盐，参数调整后用户下次登录时重新哈希
+
+use std::time::Duration;
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use serde::Deserialize;
+
+use crate::auth::constant_time_eq;
+use crate::configs::ConfigErrors;
+use crate::core::errors::{AppError, AppResult};
+
+/// 密码策略、哈希参数及登录失败锁定，可热加载
+#[serde_with::serde_as]
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PasswordConfig {
+    /// 最小长度，默认8
+    pub min_length: usize,
+    /// 最大长度，默认128，避免超长密码消耗过多资源
+    pub max_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    /// 需要包含字母、数字以外的字符
+    pub require_symbol: bool,
+    pub argon2: Argon2Config,
+    /// 连续登录失败该次数后锁定，0表示不锁定，默认5
+    pub max_failures: u32,
+    /// 锁定时长，默认15分钟
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    pub lockout: Duration,
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: false,
+            require_symbol: false,
+            argon2: Argon2Config::default(),
+            max_failures: 5,
+            lockout: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+/// Argon2id参数，默认值为OWASP推荐的最低配置
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Argon2Config {
+    /// 内存大小（KiB），默认19456
+    pub memory_kib: u32,
+    /// 迭代次数，默认2
+    pub iterations: u32,
+    /// 并行度，默认1
+    pub parallelism: u32,
+}
+
+impl Default for Argon2Config {
+    fn default() -> Self {
+        Self { memory_kib: 19 * 1024, iterations: 2, parallelism: 1 }
+    }
+}
+
+impl Argon2Config {
+    fn params(&self) -> Result<Params, argon2::Error> {
+        Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+    }
+
+    fn hasher(&self) -> AppResult<Argon2<'static>> {
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params().map_err(argon2::password_hash::Error::from)?))
+    }
+}
+
+impl PasswordConfig {
+    pub(crate) fn validate(&self, errors: &mut ConfigErrors) {
+        if self.min_length < 6 {
+            errors.push("password.min_length", "must be at least 6");
+        }
+        if self.max_length < self.min_length || self.max_length > 1024 {
+            errors.push("password.max_length", "must be between min_length and 1024");
+        }
+        if let Err(e) = self.argon2.params() {
+            errors.push("password.argon2", e.to_string());
+        }
+        if self.max_failures > 0 && self.lockout.is_zero() {
+            errors.push("password.lockout", "must not be 0 when max_failures is set");
+        }
+    }
+
+    /// 校验密码强度，密码不能包含用户名
+    pub fn check(&self, password: &str, username: &str) -> AppResult<()> {
+        let length = password.chars().count();
+        if length < self.min_length || length > self.max_length {
+            return Err(AppError::BadRequest(format!("password must be {} to {} characters", self.min_length, self.max_length)));
+        }
+        let rules = [
+            (self.require_uppercase, password.chars().any(|c| c.is_uppercase()), "an uppercase letter"),
+            (self.require_lowercase, password.chars().any(|c| c.is_lowercase()), "a lowercase letter"),
+            (self.require_digit, password.chars().any(|c| c.is_ascii_digit()), "a digit"),
+            (self.require_symbol, password.chars().any(|c| !c.is_alphanumeric()), "a symbol"),
+        ];
+        if let Some((_, _, name)) = rules.iter().find(|(required, present, _)| *required && !*present) {
+            return Err(AppError::BadRequest(format!("password must contain {}", name)));
+        }
+        if !username.is_empty() && password.to_lowercase().contains(&username.to_lowercase()) {
+            return Err(AppError::BadRequest("password must not contain the username".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// 使用随机盐哈希密码，返回PHC格式的字符串
+pub fn hash(password: &str, config: &Argon2Config) -> AppResult<String> {
+    let salt = SaltString::encode_b64(uuid::Uuid::new_v4().as_bytes())?;
+    Ok(config.hasher()?.hash_password(password.as_bytes(), &salt)?.to_string())
+}
+
+/// 校验密码，哈希为空时校验失败。不是PHC格式时视为使用哈希前保存的明文，
+/// 以常量时间比较，登录成功后按[`needs_rehash`]重新哈希
+pub fn verify(password: &str, hash: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(hash) else {
+        return !hash.is_empty() && constant_time_eq(password.as_bytes(), hash.as_bytes());
+    };
+    Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok()
+}
+
+/// 哈希计算耗时较长，在阻塞线程中执行
+pub async fn hash_blocking(password: String, config: Argon2Config) -> AppResult<String> {
+    blocking(move || hash(&password, &config)).await?
+}
+
+pub async fn verify_blocking(password: String, hash: String) -> AppResult<bool> {
+    blocking(move || verify(&password, &hash)).await
+}
+
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> AppResult<T> {
+    tokio::task::spawn_blocking(f).await.map_err(|e| AppError::StdIo(std::io::Error::other(e)))
+}
+
+/// 算法或参数与当前配置不一致时需要重新哈希
+pub fn needs_rehash(hash: &str, config: &Argon2Config) -> bool {
+    let Ok(parsed) = PasswordHash::new(hash) else {
+        return true;
+    };
+    let Ok(expected) = config.params() else {
+        return false;
+    };
+    let same_params = Params::try_from(&parsed).is_ok_and(|p| {
+        (p.m_cost(), p.t_cost(), p.p_cost()) == (expected.m_cost(), expected.t_cost(), expected.p_cost())
+    });
+    parsed.algorithm != Algorithm::Argon2id.ident() || parsed.version != Some(Version::V0x13.into()) || !same_params
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// 测试时使用较小的参数
+    pub(crate) fn weak() -> Argon2Config {
+        Argon2Config { memory_kib: 64, iterations: 1, parallelism: 1 }
+    }
+
+    #[test]
+    fn test_hash() {
+        let hashed = hash("correct horse", &weak()).unwrap();
+        assert!(hashed.starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+        assert_ne!(hashed, hash("correct horse", &weak()).unwrap());
+        assert!(verify("correct horse", &hashed));
+        assert!(!verify("wrong horse", &hashed));
+        assert!(!verify("", ""));
+        // 哈希前保存的明文
+        assert!(verify("correct horse", "correct horse"));
+        assert!(!verify("wrong horse", "correct horse"));
+
+        assert!(!needs_rehash(&hashed, &weak()));
+        assert!(needs_rehash(&hashed, &Argon2Config { iterations: 2, ..weak() }));
+        assert!(needs_rehash("plain", &weak()));
+    }
+
+    #[test]
+    fn test_check() {
+        let config = PasswordConfig { require_uppercase: true, require_digit: true, require_symbol: true, ..Default::default() };
+        let error = |password: &str| config.check(password, "alice").unwrap_err().to_string();
+        assert!(config.check("Secret-123", "alice").is_ok());
+        assert_eq!(error("Sh-1"), "password must be 8 to 128 characters");
+        assert_eq!(error("secret-123"), "password must contain an uppercase letter");
+        assert_eq!(error("Secret-abc"), "password must contain a digit");
+        assert_eq!(error("Secret1234"), "password must contain a symbol");
+        assert_eq!(error("My-ALICE-123"), "password must not contain the username");
+        // 客户端错误，返回400
+        assert!(matches!(config.check("Sh-1", "alice"), Err(AppError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,338 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use sea_orm::{Database, DatabaseConnection};
+
+use crate::configs::{AppConfig, ConfigSource};
+use crate::controller::{bind_web_service, open_openapi, start_web_service, web_openapi};
+use crate::core::context::{init_pool_opt, Context};
+use crate::core::errors::{AppError, AppResult};
+use crate::core::shutdown;
+use crate::core::version::Version;
+use crate::logging::setup_logging;
+use crate::service::email_outbox_service::EmailOutboxService;
+use crate::service::seed_service::{Fixture, SeedService, DEFAULT_SEED_SET};
+
+/// WEB模板应用
+#[derive(Debug, Parser)]
+#[command(version)]
+pub struct Cli {
+    /// 外部配置文件路径，默认读取`APP_CONFIG_PATH`环境变量
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// 激活的profile，默认读取`APP_PROFILE`环境变量
+    #[arg(long, global = true)]
+    profile: Option<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// 启动服务（默认）
+    Serve,
+    /// 数据库迁移
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+    /// 导入种子数据，已存在的用户、角色按名称更新
+    Seed {
+        /// 数据集，默认为激活的profile，未激活时为`dev`
+        #[arg(long, conflicts_with = "file")]
+        set: Option<String>,
+        /// 从YAML或JSON文件导入
+        #[arg(long, value_name = "FILE")]
+        file: Option<PathBuf>,
+        /// 导入前删除所有表并重新执行迁移，数据库中的其他数据都会丢失，只能在`dev`、`test`profile中执行
+        #[arg(long)]
+        reset: bool,
+        /// 确认删除所有表
+        #[arg(long, requires = "reset")]
+        yes: bool,
+    },
+    /// 查看、校验配置
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// 开放文档
+    Openapi {
+        #[command(subcommand)]
+        action: OpenapiAction,
+    },
+    /// 版本信息
+    Version,
+    /// 同`config check`
+    #[command(name = "check-config", hide = true)]
+    CheckConfig,
+}
+
+#[derive(Debug, Subcommand)]
+enum MigrateAction {
+    /// 执行所有未执行的迁移
+    Up {
+        /// 只输出将执行的SQL，不修改数据库
+        #[arg(long)]
+        dry_run: bool,
+        /// 执行到指定的迁移（包含）为止
+        #[arg(long, value_name = "NAME", conflicts_with = "dry_run")]
+        to: Option<String>,
+    },
+    /// 回滚迁移
+    Down {
+        /// 回滚的迁移数量
+        #[arg(long, default_value_t = 1)]
+        steps: u32,
+    },
+    /// 查看迁移状态及执行时间
+    Status,
+    /// 检查数据库与程序的迁移是否一致，不一致时返回失败
+    Check,
+    /// 删除所有表后重新执行所有迁移，只能在`dev`、`test`profile中执行
+    Fresh {
+        /// 确认删除所有表
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigAction {
+    /// 输出生效的配置（隐藏敏感信息）
+    Print,
+    /// 校验配置及数据库连接
+    Check,
+}
+
+#[derive(Debug, Subcommand)]
+enum OpenapiAction {
+    /// 导出开放文档
+    Export {
+        /// 输出文件，默认输出到标准输出
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+        /// 导出的文档
+        #[arg(long, value_enum, default_value_t = DocKind::Web)]
+        doc: DocKind,
+    },
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum DocKind {
+    /// 内部开放文档
+    Web,
+    /// 外部开放文档
+    Open,
+}
+
+impl Cli {
+    pub async fn run(self) -> AppResult<()> {
+        let source = self.source();
+        match self.command.unwrap_or(Command::Serve) {
+            Command::Serve => serve(source).await,
+            Command::Migrate { action } => {
+                if let MigrateAction::Fresh { yes } = action {
+                    confirm_drop_all(&source, yes, "migrate fresh")?;
+                }
+                migrate(source, action).await
+            }
+            Command::Seed { set, file, reset, yes } => {
+                if reset {
+                    confirm_drop_all(&source, yes, "seed --reset")?;
+                }
+                seed(source, set, file, reset).await
+            }
+            Command::Config { action: ConfigAction::Print } => print_config(source),
+            Command::Config { action: ConfigAction::Check } | Command::CheckConfig => check_config(source).await,
+            Command::Openapi {
+                action: OpenapiAction::Export { out, doc },
+            } => export_openapi(source, out, doc),
+            Command::Version => {
+                let version = Version::default();
+                println!("{} (commit: {})", version.value(), version.commit_id().trim());
+                Ok(())
+            }
+        }
+    }
+
+    /// 命令行参数优先于环境变量
+    fn source(&self) -> ConfigSource {
+        let env = ConfigSource::from_env();
+        ConfigSource::new(self.config.clone().unwrap_or(env.path), self.profile.clone().or(env.profile))
+    }
+}
+
+async fn serve(source: ConfigSource) -> AppResult<()> {
+    let config = source.load()?;
+    config.validate()?;
+    let (_guard, logging) = setup_logging(&config.logging)?;
+    let ctx = Context::new(config).await?.with_logging(logging);
+    ctx.reloader.clone().watch(source);
+    ctx.run_database_migration().await?;
+    ctx.check_schema_drift().await?;
+    // 关闭处理按添加的顺序执行，先停止接收请求，再停止后台任务
+    let server = bind_web_service(&ctx).await;
+    ctx.add_cluster_event_hook().await;
+    let ctx = Arc::new(ctx);
+    EmailOutboxService::start(ctx.clone()).await;
+    start_web_service(server, ctx).await?;
+    // 等到所有任务优雅关闭
+    shutdown::completed().await;
+    Ok(())
+}
+
+async fn migrate(source: ConfigSource, action: MigrateAction) -> AppResult<()> {
+    let config = source.load()?;
+    config.validate()?;
+    let _guard = setup_logging(&config.logging)?;
+    let db = connect(&config).await?;
+    match action {
+        MigrateAction::Up { dry_run: false, to } => {
+            let source = &config.data_source;
+            common::migration::migrate(&db, &source.url, to.as_deref(), source.migration_lock_timeout).await?
+        }
+        MigrateAction::Up { dry_run: true, .. } => {
+            for m in common::migration::dry_run(&db).await? {
+                println!("-- {}", m.name);
+                for statement in m.statements {
+                    println!("{}", statement);
+                }
+            }
+        }
+        MigrateAction::Down { steps } => common::migration::rollback(&db, Some(steps)).await?,
+        MigrateAction::Fresh { .. } => common::migration::fresh(&db).await?,
+        MigrateAction::Status => {
+            let report = common::migration::report(&db).await?;
+            for m in &report.migrations {
+                let applied_at = m.applied_at.map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_else(|| "-".to_string());
+                println!("{:<8} {:<19} {}", m.state, applied_at, m.name);
+            }
+            if let Some(drift) = report.drift() {
+                println!("{}", drift);
+            }
+        }
+        MigrateAction::Check => {
+            if let Some(drift) = common::migration::report(&db).await?.drift() {
+                return Err(AppError::SchemaDrift(drift));
+            }
+            println!("database schema is up to date.");
+        }
+    }
+    Ok(())
+}
+
+async fn seed(source: ConfigSource, set: Option<String>, file: Option<PathBuf>, reset: bool) -> AppResult<()> {
+    let config = source.load()?;
+    config.validate()?;
+    let _guard = setup_logging(&config.logging)?;
+    let fixture = match file {
+        Some(path) => Fixture::from_file(&path)?,
+        None => Fixture::load(&source, set.as_deref().or(source.profile.as_deref()).unwrap_or(DEFAULT_SEED_SET))?,
+    };
+    let ctx = Context::new(config).await?;
+    let report = if reset { SeedService::reset(&ctx, &fixture).await? } else { SeedService::load(&ctx, &fixture).await? };
+    println!("{}", report);
+    Ok(())
+}
+
+/// 删除所有表的命令需要`--yes`确认，且只能在`dev`、`test`profile中执行
+fn confirm_drop_all(source: &ConfigSource, yes: bool, command: &str) -> AppResult<()> {
+    if !matches!(source.profile.as_deref(), Some("dev" | "test")) {
+        let profile = source.profile.as_deref().unwrap_or("none");
+        return Err(AppError::Cli(format!("`{}` drops all tables and is only allowed with the dev or test profile, current profile: {}", command, profile)));
+    }
+    if !yes {
+        return Err(AppError::Cli(format!("`{}` drops all tables, add --yes to confirm", command)));
+    }
+    Ok(())
+}
+
+async fn connect(config: &AppConfig) -> AppResult<DatabaseConnection> {
+    Ok(Database::connect(init_pool_opt(&config.data_source)).await?)
+}
+
+fn print_config(source: ConfigSource) -> AppResult<()> {
+    let config = source.load()?;
+    println!("{:#?}", config);
+    Ok(())
+}
+
+async fn check_config(source: ConfigSource) -> AppResult<()> {
+    source.load()?.check().await?;
+    println!("configuration is valid.");
+    Ok(())
+}
+
+fn export_openapi(source: ConfigSource, out: Option<PathBuf>, doc: DocKind) -> AppResult<()> {
+    let config = source.load()?;
+    let version = Version::default();
+    let doc = match doc {
+        DocKind::Web => web_openapi(&version, &config.openapi),
+        DocKind::Open => open_openapi(&version, &config.openapi),
+    };
+    let json = doc.to_pretty_json()?;
+    match out {
+        Some(path) => std::fs::write(path, json)?,
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> AppResult<()> {
+        let cli = Cli::try_parse_from([&["application", "--config", "/not/exists/application.yaml"], args].concat()).unwrap();
+        tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(cli.run())
+    }
+
+    #[test]
+    fn test_parse() {
+        let cli = Cli::try_parse_from(["application", "--profile", "dev", "migrate", "up", "--to", "m20261018_000001_create_app_key_table"]).unwrap();
+        assert_eq!(cli.profile.as_deref(), Some("dev"));
+        assert!(matches!(cli.command, Some(Command::Migrate { action: MigrateAction::Up { dry_run: false, to: Some(_) } })));
+        // 全局参数可以放在子命令之后
+        let cli = Cli::try_parse_from(["application", "migrate", "down", "--config", "app.yaml"]).unwrap();
+        assert_eq!(cli.config, Some(PathBuf::from("app.yaml")));
+        assert!(matches!(cli.command, Some(Command::Migrate { action: MigrateAction::Down { steps: 1 } })));
+        assert!(Cli::try_parse_from(["application"]).unwrap().command.is_none());
+        assert!(matches!(Cli::try_parse_from(["application", "check-config"]).unwrap().command, Some(Command::CheckConfig)));
+        let cli = Cli::try_parse_from(["application", "openapi", "export", "--doc", "open"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Openapi { action: OpenapiAction::Export { out: None, doc: DocKind::Open } })));
+
+        assert!(Cli::try_parse_from(["application", "migrate", "up", "--dry-run", "--to", "x"]).is_err());
+        assert!(Cli::try_parse_from(["application", "seed", "--set", "dev", "--file", "a.yaml"]).is_err());
+        assert!(Cli::try_parse_from(["application", "seed", "--yes"]).is_err());
+        assert!(Cli::try_parse_from(["application", "unknown"]).is_err());
+    }
+
+    #[test]
+    fn test_confirm_drop_all() {
+        let error = run(&["--profile", "prod", "seed", "--reset", "--yes"]).unwrap_err().to_string();
+        assert!(error.contains("only allowed with the dev or test profile, current profile: prod"), "{}", error);
+        let error = run(&["--profile", "dev", "seed", "--reset"]).unwrap_err().to_string();
+        assert_eq!(error, "`seed --reset` drops all tables, add --yes to confirm");
+        let error = run(&["migrate", "fresh", "--yes"]).unwrap_err().to_string();
+        assert!(error.contains("current profile: none"), "{}", error);
+        let error = run(&["--profile", "test", "migrate", "fresh"]).unwrap_err().to_string();
+        assert_eq!(error, "`migrate fresh` drops all tables, add --yes to confirm");
+    }
+
+    /// 子命令分发到对应的处理，配置不存在时使用内置的默认配置
+    #[test]
+    fn test_dispatch() {
+        assert!(run(&["version"]).is_ok());
+        assert!(run(&["config", "print"]).is_ok());
+        let out = std::env::temp_dir().join(format!("openapi-{}.json", uuid::Uuid::new_v4().simple()));
+        run(&["openapi", "export", "--doc", "open", "--out", out.to_str().unwrap()]).unwrap();
+        let doc: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+        assert!(doc["components"]["securitySchemes"]["App-Token"].is_object(), "{}", doc);
+        std::fs::remove_file(out).unwrap();
+        // 未知的数据集在连接数据库前失败
+        let error = run(&["seed", "--set", "unknown"]).unwrap_err().to_string();
+        assert_eq!(error, "invalid seed data: can not find seed set `unknown`");
+    }
+}
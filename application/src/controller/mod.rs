@@ -1,5 +1,3 @@
-use std::any::Any;
-use std::error::Error;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -8,12 +6,16 @@ use crate::configs;
 use crate::core::context::Context;
 use crate::core::errors::AppResult;
 use crate::core::salvo::context_inject::ContextInject;
+use crate::core::salvo::health;
 use crate::core::salvo::logger::TraceLogger;
-use crate::core::salvo::HEADER_APP_TOKEN;
+use crate::core::salvo::metrics::{metrics, HttpMetrics, MetricsGuard, RouteMatched};
+use crate::core::salvo::reloadable::{DocSwitch, ReloadableCors};
+use crate::core::salvo::{HEADER_APP_TOKEN, SECURITY_APP, SECURITY_WEB};
 use crate::core::shutdown;
 use crate::core::version::Version;
+use salvo::conn::tcp::TcpAcceptor;
 use salvo::conn::TcpListener;
-use salvo::cors::{AllowHeaders, AllowMethods, AllowOrigin, Cors, CorsHandler};
+use tokio::sync::oneshot;
 use salvo::oapi::security::{ApiKey, ApiKeyValue, Http, HttpAuthScheme};
 use salvo::oapi::{Contact, Info, OpenApi, SecurityRequirement, SecurityScheme, Server as OpenApiServer};
 use salvo::serve_static::static_embed;
@@ -24,40 +26,64 @@ use crate::core::rapidoc::RapiDoc;
 mod openapi;
 mod web;
 
-pub async fn start_web_service(ctx: Arc<Context>) -> AppResult<()> {
-    let config = &ctx.config;
-    let path = config.path();
-    let context = ctx.clone();
-    let server = Server::new(TcpListener::new(context.config.server.to_string()).bind().await);
+/// 已监听端口、尚未处理请求的服务
+pub struct WebServer {
+    server: Server<TcpAcceptor>,
+    stopped: oneshot::Sender<()>,
+}
+
+/// 监听端口并添加关闭处理：等待负载均衡摘除后停止接收请求，已接收的请求处理完后才执行之后添加的关闭处理，
+/// 需要在集群事件、发件箱等后台任务添加关闭处理之前调用
+pub async fn bind_web_service(ctx: &Context) -> WebServer {
+    let server = Server::new(TcpListener::new(ctx.config.server.to_string()).bind().await);
     let handle = server.handle();
-    shutdown::push(async move { handle.stop_graceful(Duration::from_secs(30)) }).await;
+    let drain_delay = ctx.config.server.shutdown_drain_delay;
+    let (stopped, stopped_rx) = oneshot::channel();
+    shutdown::push(async move {
+        // 退出标志已设置，就绪检查返回失败，等待负载均衡摘除后再停止接收请求
+        tokio::time::sleep(drain_delay).await;
+        handle.stop_graceful(Duration::from_secs(30));
+        let _ = stopped_rx.await;
+    })
+    .await;
+    WebServer { server, stopped }
+}
 
+pub async fn start_web_service(server: WebServer, ctx: Arc<Context>) -> AppResult<()> {
+    salvo::http::request::set_global_secure_max_size(1024 * 1024);
+    server.server.serve(service(ctx)).await;
+    let _ = server.stopped.send(());
+    Ok(())
+}
+
+/// 所有路由及全局中间件，测试时可直接通过`TestClient`调用
+pub(crate) fn service(ctx: Arc<Context>) -> Service {
+    let config = &ctx.config;
+    let path = config.path();
     let static_api = add_static(Router::new());
-    let (mut webapi, webdoc) = merge_router(&ctx.version, || web::router());
-    let (mut openapi, opendoc) = merge_router(&ctx.version, || openapi::router());
+    let (mut webapi, webdoc) = merge_router(&ctx.version, web::router);
+    let (mut openapi, opendoc) = merge_router(&ctx.version, openapi::router);
 
     // 内部添加cors，开发环境可开放，其他环境则严格设置
-    if let Some(cors) = add_cors(&config.openapi) {
-        webapi = webapi.hoop(cors.clone());
-        openapi = openapi.hoop(cors);
-    }
+    let cors = ReloadableCors::default();
+    webapi = webapi.hoop(cors.clone());
+    openapi = openapi.hoop(cors);
 
     // 合并所有请求路由
     let mut all_routers = if path.is_empty() { Router::new() } else { Router::with_path(&path) };
+    all_routers = all_routers.hoop(RouteMatched);
 
     // 返回内容有512kb时开启压缩
     let compression = Compression::new().disable_all();
     all_routers = all_routers.push(static_api.hoop(compression.clone()));
+    all_routers = all_routers.push(Router::with_path("metrics").hoop(MetricsGuard).get(metrics));
+    all_routers = all_routers.push(health::router());
     all_routers = add_web_doc(webdoc, &config.openapi, all_routers);
     all_routers = add_open_doc(opendoc, &config.openapi, all_routers);
     all_routers = all_routers.push(webapi.hoop(compression.clone()));
     all_routers = all_routers.push(openapi.hoop(compression.clone()));
 
-    let web = Service::new(all_routers).hoop(ContextInject { context: ctx.clone() }).hoop(TraceLogger);
-
-    salvo::http::request::set_global_secure_max_size(1024 * 1024);
-    server.serve(web).await;
-    Ok(())
+    Service::new(all_routers).hoop(ContextInject { context: ctx.clone() }).hoop(TraceLogger).hoop(HttpMetrics)
 }
 
 /// 合并路由
@@ -70,24 +96,6 @@ where
     (router, doc)
 }
 
-/// 创建cors
-fn add_cors(openapi_config: &configs::OpenApi) -> Option<CorsHandler> {
-    if openapi_config.cors_origin.is_empty() {
-        return None;
-    }
-    let origin = match openapi_config.cors_origin.contains(&String::from("*")) {
-        true => AllowOrigin::any(),
-        _ => (&openapi_config.cors_origin).into(),
-    };
-
-    let cors = Cors::new()
-        .allow_origin(origin)
-        .allow_methods(AllowMethods::any())
-        .allow_headers(AllowHeaders::any())
-        .into_handler();
-    Some(cors)
-}
-
 /// 创建开放文档
 fn create_openapi(v: &Version) -> OpenApi {
     let info = Info::new("WEB模板应用", v.to_string())
@@ -102,38 +110,52 @@ fn add_static(ar: Router) -> Router {
         .push(Router::with_path("static/<*path>").get(static_embed::<AssetsStatic>()))
 }
 
+/// 内部Openapi Doc，用于导出
+pub fn web_openapi(version: &Version, config: &configs::OpenApi) -> OpenApi {
+    let (_, doc) = merge_router(version, web::router);
+    web_doc(doc, config)
+}
+
+/// 对外Openapi Doc，用于导出
+pub fn open_openapi(version: &Version, config: &configs::OpenApi) -> OpenApi {
+    let (_, doc) = merge_router(version, openapi::router);
+    open_doc(doc, config)
+}
+
+fn web_doc(doc: OpenApi, config: &configs::OpenApi) -> OpenApi {
+    doc.servers([OpenApiServer::new(config.server.as_str())])
+        .add_security_scheme(SECURITY_WEB, SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer).bearer_format("JWT")))
+        .security(vec![SecurityRequirement::new(SECURITY_WEB, Vec::<String>::new())])
+}
+
+fn open_doc(doc: OpenApi, config: &configs::OpenApi) -> OpenApi {
+    doc.servers([OpenApiServer::new(config.server.as_str())])
+        .add_security_scheme(SECURITY_APP, SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(HEADER_APP_TOKEN))))
+        .security(vec![SecurityRequirement::new(SECURITY_APP, Vec::<String>::new())])
+}
+
 /// 添加内部Openapi Doc
 fn add_web_doc(doc: OpenApi, config: &configs::OpenApi, all_routers: Router) -> Router {
-    if !config.enable_web_doc {
-        return all_routers;
-    }
-    let server_doc = doc
-        .servers([OpenApiServer::new(config.server.as_str())])
-        .add_security_scheme("Qbee", SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer).bearer_format("Bear")))
-        .security(vec![SecurityRequirement::new("Qbee", ["edit:items", "read:items"])]);
-
-    all_routers.push(server_doc.into_router("server-api-doc/openapi.json")).push(
+    let server_doc = web_doc(doc, config);
+
+    let doc_routers = Router::new().hoop(DocSwitch::Web).push(server_doc.into_router("server-api-doc/openapi.json")).push(
         RapiDoc::new("server-api-doc/openapi.json")
             .title("TagFlow Openapi")
             .lib_url("openapi-doc/rapidoc-min.js")
             .into_router("/doc.html"),
-    )
+    );
+    all_routers.push(doc_routers)
 }
 
 /// 添加对外Openapi Doc
 fn add_open_doc(oo: OpenApi, config: &configs::OpenApi, all_routers: Router) -> Router {
-    if !config.enable_open_doc {
-        return all_routers;
-    }
-    let external_doc = oo
-        .servers([OpenApiServer::new(config.server.as_str())])
-        .add_security_scheme("App-Token", SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(HEADER_APP_TOKEN))))
-        .security(vec![SecurityRequirement::new("App-Token", ["edit:items", "read:items"])]);
-
-    all_routers.push(external_doc.into_router("external-api-doc/openapi.json")).push(
+    let external_doc = open_doc(oo, config);
+
+    let doc_routers = Router::new().hoop(DocSwitch::Open).push(external_doc.into_router("external-api-doc/openapi.json")).push(
         RapiDoc::new("external-api-doc/openapi.json")
             .title("TagFlow Openapi")
             .lib_url("openapi-doc/rapidoc-min.js")
             .into_router("open-doc.html"),
-    )
+    );
+    all_routers.push(doc_routers)
 }
@@ -1,11 +1,12 @@
 use salvo::{handler, Router};
 
+use crate::core::salvo::auth::AppTokenAuth;
+
 #[handler]
 async fn hello() -> &'static str {
     "Hello World"
 }
 
 pub(crate) fn router() -> Router {
-    let router = Router::new().path("open").get(hello);
-    router
+    Router::new().path("open").hoop(AppTokenAuth).get(hello)
 }
@@ -0,0 +1,71 @@
+use salvo::oapi::endpoint;
+use salvo::oapi::extract::JsonBody;
+use salvo::prelude::Text;
+use salvo::{handler, Depot, Response, Router, Writer};
+
+use crate::core::errors::AppResult;
+use crate::core::salvo::api_result::ResponseResult;
+use crate::core::salvo::context_inject::obtain_context;
+use crate::service::account_service::{AccountService, EmailRequest, ResetPassword, Signup, VerifyEmail};
+use crate::service::user_service::UserVo;
+
+/// 注册，注册后需要验证邮箱才能登录
+#[endpoint(tags("账号"), security(()))]
+async fn signup(depot: &mut Depot, body: JsonBody<Signup>) -> AppResult<ResponseResult<'static, UserVo>> {
+    let ctx = obtain_context(depot)?;
+    Ok(ResponseResult::ok(AccountService::signup(ctx, body.into_inner()).await?))
+}
+
+/// 验证邮件中的链接打开的页面，页面提交令牌
+#[handler]
+async fn verify_email_page(res: &mut Response) {
+    res.render(Text::Html(include_str!("../../../templates/pages/verify_email.html")));
+}
+
+/// 验证邮箱，令牌来自验证邮件中的链接
+#[endpoint(tags("账号"), security(()))]
+async fn verify_email(depot: &mut Depot, body: JsonBody<VerifyEmail>) -> AppResult<ResponseResult<'static, ()>> {
+    let ctx = obtain_context(depot)?;
+    AccountService::verify_email(ctx, &body.into_inner().token).await?;
+    Ok(ResponseResult::ok(()))
+}
+
+/// 重新发送验证邮件
+#[endpoint(tags("账号"), security(()))]
+async fn resend_verification(depot: &mut Depot, body: JsonBody<EmailRequest>) -> AppResult<ResponseResult<'static, ()>> {
+    let ctx = obtain_context(depot)?;
+    AccountService::resend_verification(ctx, body.into_inner()).await?;
+    Ok(ResponseResult::ok(()))
+}
+
+/// 忘记密码，发送重置密码邮件
+#[endpoint(tags("账号"), security(()))]
+async fn forgot_password(depot: &mut Depot, body: JsonBody<EmailRequest>) -> AppResult<ResponseResult<'static, ()>> {
+    let ctx = obtain_context(depot)?;
+    AccountService::forgot_password(ctx, body.into_inner()).await?;
+    Ok(ResponseResult::ok(()))
+}
+
+/// 重置密码邮件中的链接打开的页面，页面提交令牌及新密码
+#[handler]
+async fn reset_password_page(res: &mut Response) {
+    res.render(Text::Html(include_str!("../../../templates/pages/reset_password.html")));
+}
+
+/// 使用重置密码邮件中的令牌设置新密码
+#[endpoint(tags("账号"), security(()))]
+async fn reset_password(depot: &mut Depot, body: JsonBody<ResetPassword>) -> AppResult<ResponseResult<'static, ()>> {
+    let ctx = obtain_context(depot)?;
+    AccountService::reset_password(ctx, body.into_inner()).await?;
+    Ok(ResponseResult::ok(()))
+}
+
+/// 不需要认证
+pub(crate) fn router() -> Router {
+    Router::new()
+        .push(Router::with_path("signup").post(signup))
+        .push(Router::with_path("email/verify").get(verify_email_page).post(verify_email))
+        .push(Router::with_path("email/verify/resend").post(resend_verification))
+        .push(Router::with_path("password/forgot").post(forgot_password))
+        .push(Router::with_path("password/reset").get(reset_password_page).post(reset_password))
+}
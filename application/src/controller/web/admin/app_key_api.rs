@@ -0,0 +1,36 @@
+use salvo::oapi::endpoint;
+use salvo::oapi::extract::{JsonBody, PathParam};
+use salvo::{Depot, Router, Writer};
+
+use crate::core::errors::AppResult;
+use crate::core::salvo::api_result::ResponseResult;
+use crate::core::salvo::context_inject::obtain_context;
+use crate::service::app_key_service::{AppKeyService, AppKeyVo, CreateAppKey, CreatedAppKey};
+
+/// 查询所有外部应用密钥
+#[endpoint(tags("系统管理"))]
+async fn list_keys(depot: &mut Depot) -> AppResult<ResponseResult<'static, Vec<AppKeyVo>>> {
+    let ctx = obtain_context(depot)?;
+    Ok(ResponseResult::ok(AppKeyService::list(ctx).await?))
+}
+
+/// 创建外部应用密钥，密钥明文只返回一次
+#[endpoint(tags("系统管理"))]
+async fn create_key(depot: &mut Depot, body: JsonBody<CreateAppKey>) -> AppResult<ResponseResult<'static, CreatedAppKey>> {
+    let ctx = obtain_context(depot)?;
+    Ok(ResponseResult::ok(AppKeyService::create(ctx, body.into_inner()).await?))
+}
+
+/// 吊销外部应用密钥
+#[endpoint(tags("系统管理"), parameters(("id", description = "密钥ID")))]
+async fn revoke_key(depot: &mut Depot, id: PathParam<i64>) -> AppResult<ResponseResult<'static, AppKeyVo>> {
+    let ctx = obtain_context(depot)?;
+    Ok(ResponseResult::ok(AppKeyService::revoke(ctx, id.into_inner()).await?))
+}
+
+pub(crate) fn router() -> Router {
+    Router::with_path("app-keys")
+        .get(list_keys)
+        .post(create_key)
+        .push(Router::with_path("<id:num>").delete(revoke_key))
+}
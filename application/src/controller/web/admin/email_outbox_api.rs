@@ -0,0 +1,28 @@
+use salvo::oapi::endpoint;
+use salvo::oapi::extract::PathParam;
+use salvo::{Depot, Router, Writer};
+
+use crate::core::errors::AppResult;
+use crate::core::salvo::api_result::ResponseResult;
+use crate::core::salvo::context_inject::obtain_context;
+use crate::service::email_outbox_service::{EmailOutboxService, OutboxVo};
+
+/// 查询多次发送失败的邮件
+#[endpoint(tags("系统管理"))]
+async fn list_dead(depot: &mut Depot) -> AppResult<ResponseResult<'static, Vec<OutboxVo>>> {
+    let ctx = obtain_context(depot)?;
+    Ok(ResponseResult::ok(EmailOutboxService::list_dead(ctx).await?))
+}
+
+/// 重新发送失败的邮件
+#[endpoint(tags("系统管理"), parameters(("id", description = "邮件ID")))]
+async fn retry(depot: &mut Depot, id: PathParam<i64>) -> AppResult<ResponseResult<'static, OutboxVo>> {
+    let ctx = obtain_context(depot)?;
+    Ok(ResponseResult::ok(EmailOutboxService::retry(ctx, id.into_inner()).await?))
+}
+
+pub(crate) fn router() -> Router {
+    Router::with_path("email-outbox")
+        .push(Router::with_path("dead").get(list_dead))
+        .push(Router::with_path("<id:num>/retry").post(retry))
+}
@@ -0,0 +1,62 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use salvo::oapi::extract::{JsonBody, QueryParam};
+use salvo::oapi::{endpoint, ToSchema};
+use salvo::{Depot, Router, Writer};
+use serde::Deserialize;
+use tracing::level_filters::LevelFilter;
+
+use crate::core::context::Context;
+use crate::core::errors::{AppError, AppResult};
+use crate::core::salvo::api_result::ResponseResult;
+use crate::core::salvo::context_inject::obtain_context;
+use crate::logging::{EffectiveLevels, LoggingHandle};
+
+/// 修改日志等级
+#[derive(Debug, Deserialize, ToSchema)]
+struct SetLevel {
+    /// 模块，eg:`sqlx::query`，为空时修改默认日志等级
+    target: Option<String>,
+    /// 日志等级：`off`/`error`/`warn`/`info`/`debug`/`trace`
+    level: String,
+    /// 生效时长（秒），到期后恢复为配置的等级，为空时一直生效
+    ttl_secs: Option<u64>,
+}
+
+/// 查询当前生效的日志等级
+#[endpoint(tags("系统管理"))]
+async fn get_levels(depot: &mut Depot) -> AppResult<ResponseResult<'static, EffectiveLevels>> {
+    let handle = logging_handle(obtain_context(depot)?)?;
+    Ok(ResponseResult::ok(handle.levels()))
+}
+
+/// 临时修改日志等级
+#[endpoint(tags("系统管理"))]
+async fn set_level(depot: &mut Depot, body: JsonBody<SetLevel>) -> AppResult<ResponseResult<'static, EffectiveLevels>> {
+    let handle = logging_handle(obtain_context(depot)?)?;
+    let body = body.into_inner();
+    let level = LevelFilter::from_str(&body.level.to_lowercase()).map_err(|e| AppError::BadRequest(e.to_string()))?;
+    if body.ttl_secs == Some(0) {
+        return Err(AppError::BadRequest("ttl_secs must be greater than 0".to_string()));
+    }
+    handle.set(body.target, level, body.ttl_secs.map(Duration::from_secs))?;
+    Ok(ResponseResult::ok(handle.levels()))
+}
+
+/// 恢复为配置的日志等级
+#[endpoint(tags("系统管理"), parameters(("target", description = "模块，为空时恢复所有修改")))]
+async fn reset_level(depot: &mut Depot, target: QueryParam<String, false>) -> AppResult<ResponseResult<'static, EffectiveLevels>> {
+    let handle = logging_handle(obtain_context(depot)?)?;
+    handle.reset(target.as_deref())?;
+    Ok(ResponseResult::ok(handle.levels()))
+}
+
+fn logging_handle(ctx: &Arc<Context>) -> AppResult<&LoggingHandle> {
+    ctx.logging.as_ref().ok_or_else(|| AppError::NotFound("logging is not initialized".to_string()))
+}
+
+pub(crate) fn router() -> Router {
+    Router::with_path("logging").get(get_levels).put(set_level).delete(reset_level)
+}
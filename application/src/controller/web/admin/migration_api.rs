@@ -0,0 +1,25 @@
+use salvo::oapi::endpoint;
+use salvo::{Depot, Router};
+
+use crate::core::errors::AppResult;
+use crate::core::salvo::api_result::ResponseResult;
+use crate::core::salvo::context_inject::obtain_context;
+use crate::service::migration_service::{MigrationReportVo, MigrationService, MigrationSqlVo};
+
+/// 查询数据库迁移的执行状态
+#[endpoint(tags("系统管理"))]
+async fn report(depot: &mut Depot) -> AppResult<ResponseResult<'static, MigrationReportVo>> {
+    let ctx = obtain_context(depot)?;
+    Ok(ResponseResult::ok(MigrationService::report(ctx).await?))
+}
+
+/// 查询未执行的迁移将执行的SQL，不修改数据库
+#[endpoint(tags("系统管理"))]
+async fn dry_run(depot: &mut Depot) -> AppResult<ResponseResult<'static, Vec<MigrationSqlVo>>> {
+    let ctx = obtain_context(depot)?;
+    Ok(ResponseResult::ok(MigrationService::dry_run(ctx).await?))
+}
+
+pub(crate) fn router() -> Router {
+    Router::with_path("migrations").get(report).push(Router::with_path("dry-run").get(dry_run))
+}
@@ -0,0 +1,12 @@
+use salvo::Router;
+
+use crate::core::salvo::admin::AdminGuard;
+
+mod app_key_api;
+mod email_outbox_api;
+mod logging_api;
+mod migration_api;
+
+pub(crate) fn router() -> Router {
+    Router::with_path("admin").hoop(AdminGuard).push(logging_api::router()).push(app_key_api::router()).push(email_outbox_api::router()).push(migration_api::router())
+}
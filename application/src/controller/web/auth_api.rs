@@ -0,0 +1,40 @@
+use salvo::oapi::endpoint;
+use salvo::oapi::extract::JsonBody;
+use salvo::{Depot, Router, Writer};
+
+use crate::core::errors::AppResult;
+use crate::core::salvo::api_result::ResponseResult;
+use crate::core::salvo::auth::obtain_principal;
+use crate::core::salvo::context_inject::obtain_context;
+use crate::service::auth_service::{AuthService, ChangePassword, Login, RefreshToken, Tokens};
+
+/// 用户名密码登录，返回访问令牌及刷新令牌
+#[endpoint(tags("认证"), security(()))]
+async fn login(depot: &mut Depot, body: JsonBody<Login>) -> AppResult<ResponseResult<'static, Tokens>> {
+    let ctx = obtain_context(depot)?;
+    Ok(ResponseResult::ok(AuthService::login(ctx, body.into_inner()).await?))
+}
+
+/// 使用刷新令牌换取新的令牌
+#[endpoint(tags("认证"), security(()))]
+async fn refresh(depot: &mut Depot, body: JsonBody<RefreshToken>) -> AppResult<ResponseResult<'static, Tokens>> {
+    let ctx = obtain_context(depot)?;
+    Ok(ResponseResult::ok(AuthService::refresh(ctx, body.into_inner()).await?))
+}
+
+/// 修改当前用户的密码，返回新的令牌
+#[endpoint(tags("认证"))]
+async fn change_password(depot: &mut Depot, body: JsonBody<ChangePassword>) -> AppResult<ResponseResult<'static, Tokens>> {
+    let ctx = obtain_context(depot)?;
+    let principal = obtain_principal(depot)?;
+    Ok(ResponseResult::ok(AuthService::change_password(ctx, principal, body.into_inner()).await?))
+}
+
+/// 不需要认证的接口
+pub(crate) fn public_router() -> Router {
+    Router::new().push(Router::with_path("login").post(login)).push(Router::with_path("token/refresh").post(refresh))
+}
+
+pub(crate) fn router() -> Router {
+    Router::with_path("password").put(change_password)
+}
@@ -1,5 +1,10 @@
 use salvo::{handler, Router};
 
+use crate::core::salvo::auth::JwtAuth;
+
+mod account_api;
+mod admin;
+mod auth_api;
 mod user_api;
 
 #[handler]
@@ -8,5 +13,7 @@ async fn hello() -> &'static str {
 }
 
 pub(crate) fn router() -> Router {
-    Router::new().get(hello).push(user_api::router())
+    // 管理接口使用单独的令牌，登录接口不需要认证
+    let api = Router::new().hoop(JwtAuth).get(hello).push(auth_api::router()).push(user_api::router());
+    Router::new().push(admin::router()).push(auth_api::public_router()).push(account_api::router()).push(api)
 }
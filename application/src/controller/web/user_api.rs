@@ -1,20 +1,20 @@
 use salvo::{
     Depot,
-    oapi::{endpoint, extract::PathParam}, Router, Writer,
+    oapi::{endpoint, extract::{JsonBody, PathParam}}, Router, Writer,
 };
 
+use crate::auth::{PERMISSION_USER_READ, PERMISSION_USER_WRITE};
 use crate::core::errors::AppResult;
-use crate::core::salvo::api_result::ResponseResult;
+use crate::core::salvo::auth::permit;
+use crate::core::salvo::api_result::{Page, ResponseResult};
 use crate::core::salvo::context_inject::obtain_context;
-use crate::service::user_service::{UserService, UserVo};
+use crate::core::salvo::SECURITY_WEB;
+use crate::service::user_service::{SaveUser, SetRoles, UserQuery, UserService, UserVo};
 
 /// 查询用户信息
 #[endpoint(tags("用户管理"), parameters(("id", description = "用户ID")))]
 async fn get_ins(depot: &mut Depot, id: PathParam<i64>) -> AppResult<ResponseResult<'static, UserVo>> {
     let ctx = obtain_context(depot)?;
-    tracing::info!("this is info log in controller.");
-    tracing::debug!("this is debug log in controller.");
-    tracing::event!(tracing::Level::INFO,label=2,"this is event log in controller.");
     let user = UserService::find_by_id(ctx, id.into_inner()).await?;
     Ok(ResponseResult::ok(user))
 }
@@ -23,15 +23,52 @@ async fn get_ins(depot: &mut Depot, id: PathParam<i64>) -> AppResult<ResponseRes
 #[endpoint(tags("用户管理"), parameters(("id", description = "用户ID")))]
 async fn get_spawn(depot: &mut Depot, id: PathParam<i64>) -> AppResult<ResponseResult<'static, UserVo>> {
     let ctx = obtain_context(depot)?;
-    tracing::info!("this is info log in controller.");
-    tracing::debug!("this is debug log in controller.");
-    tracing::event!(tracing::Level::INFO,label=2,"this is event log in controller.");
     let user = UserService::find_by_id(ctx, id.into_inner()).await?;
     Ok(ResponseResult::ok(user))
 }
 
+/// 分页查询用户
+#[endpoint(tags("用户管理"))]
+async fn list(depot: &mut Depot, query: UserQuery) -> AppResult<ResponseResult<'static, Page<UserVo>>> {
+    let ctx = obtain_context(depot)?;
+    Ok(ResponseResult::ok(UserService::find_page(ctx, query).await?))
+}
+
+/// 新增用户
+#[endpoint(tags("用户管理"))]
+async fn create(depot: &mut Depot, body: JsonBody<SaveUser>) -> AppResult<ResponseResult<'static, UserVo>> {
+    let ctx = obtain_context(depot)?;
+    Ok(ResponseResult::ok(UserService::create(ctx, body.into_inner()).await?))
+}
+
+/// 修改用户
+#[endpoint(tags("用户管理"), parameters(("id", description = "用户ID")))]
+async fn update(depot: &mut Depot, id: PathParam<i64>, body: JsonBody<SaveUser>) -> AppResult<ResponseResult<'static, UserVo>> {
+    let ctx = obtain_context(depot)?;
+    Ok(ResponseResult::ok(UserService::update(ctx, id.into_inner(), body.into_inner()).await?))
+}
+
+/// 删除用户
+#[endpoint(tags("用户管理"), parameters(("id", description = "用户ID")))]
+async fn delete(depot: &mut Depot, id: PathParam<i64>) -> AppResult<ResponseResult<'static, UserVo>> {
+    let ctx = obtain_context(depot)?;
+    Ok(ResponseResult::ok(UserService::delete(ctx, id.into_inner()).await?))
+}
+
+/// 修改用户的角色，替换原有的所有角色
+#[endpoint(tags("用户管理"), parameters(("id", description = "用户ID")))]
+async fn set_roles(depot: &mut Depot, id: PathParam<i64>, body: JsonBody<SetRoles>) -> AppResult<ResponseResult<'static, UserVo>> {
+    let ctx = obtain_context(depot)?;
+    Ok(ResponseResult::ok(UserService::set_roles(ctx, id.into_inner(), body.into_inner().role_ids).await?))
+}
+
 pub(crate) fn router() -> Router {
+    let read = |router| permit(router, SECURITY_WEB, &[PERMISSION_USER_READ]);
+    let write = |router| permit(router, SECURITY_WEB, &[PERMISSION_USER_WRITE]);
     Router::with_path("user")
-        .push(Router::with_path("ins/<id:num>").get(get_ins))
-        .push(Router::with_path("spawn/<id:num>").get(get_spawn))
-}
\ No newline at end of file
+        .push(read(Router::new().get(list)))
+        .push(write(Router::new().post(create)))
+        .push(Router::with_path("ins/<id:num>").push(read(Router::new().get(get_ins))).push(write(Router::new().put(update).delete(delete))))
+        .push(write(Router::with_path("ins/<id:num>/roles").put(set_roles)))
+        .push(read(Router::with_path("spawn/<id:num>").get(get_spawn)))
+}
@@ -1,35 +1,92 @@
-use crate::configs::{AppConfig, DataSource};
-use crate::core::errors::AppResult;
+use crate::auth::jwt::{JwtIssuer, JwtVerifier};
+use crate::configs::{AppConfig, DataSource, DriftCheck, MigrationPolicy};
+use crate::core::errors::{AppError, AppResult};
+use crate::core::health::HealthChecks;
+use crate::core::metrics::Metrics;
+use crate::core::reload::ConfigReloader;
 use crate::core::shutdown;
 use crate::core::version::Version;
+use crate::logging::LoggingHandle;
+use crate::service::permission_service::PermissionCache;
+use common::email::{DisabledTransport, EmailService};
 use common::queue::broadcast::TokioSender;
 use common::queue::cluster_event::ClusterEventSender;
-use common::{env, migration};
+use common::migration::{self, SchemaDrift};
 use sea_orm::{Database, DatabaseConnection};
 use std::sync::Arc;
 use std::time::Duration;
 
+/// 默认的发件人
+const DEFAULT_MAIL_FROM: &str = "noreply@localhost";
+
 pub struct Context {
+    /// 启动时的配置，可热加载的配置项请通过`reloader`获取
     pub config: Arc<AppConfig>,
+    pub reloader: Arc<ConfigReloader>,
     pub version: Arc<Version>,
     pub db: Arc<DatabaseConnection>,
     pub cluster_event: Arc<ClusterEventSender>,
+    pub metrics: Arc<Metrics>,
+    /// 未配置`jwt`时为空
+    pub jwt: Option<Arc<JwtVerifier>>,
+    /// 可以签发令牌时才能登录，`RS256`需要配置私钥
+    pub jwt_issuer: Option<Arc<JwtIssuer>>,
+    /// 发送邮件，测试时可替换为内存发送
+    pub email: Arc<EmailService>,
+    /// 就绪检查项，业务可注册自己的检查项
+    pub health: Arc<HealthChecks>,
+    /// 用户权限的缓存
+    pub permissions: Arc<PermissionCache>,
+    /// 运行期间修改日志等级，未初始化日志时为空
+    pub logging: Option<LoggingHandle>,
 }
 
 impl Context {
     pub(crate) async fn new(config: AppConfig) -> AppResult<Context> {
         let config: Arc<AppConfig> = config.into();
         let db: DatabaseConnection = Database::connect(init_pool_opt(&config.data_source)).await?;
-        let sender = ClusterEventSender::Queue(TokioSender::new(500));
+        let db = Arc::new(db);
+        let sender = Arc::new(ClusterEventSender::Queue(TokioSender::new(500)));
+        let metrics = Metrics::new(db.clone(), sender.clone())?;
+        let jwt = config.jwt.as_ref().map(JwtVerifier::new).transpose()?.map(Arc::new);
+        if jwt.is_none() {
+            tracing::warn!("jwt is not configured, all web api requests will be rejected.");
+        }
+        let jwt_issuer = config.jwt.as_ref().map(JwtIssuer::new).transpose()?.flatten().map(Arc::new);
+        if jwt.is_some() && jwt_issuer.is_none() {
+            tracing::warn!("jwt.private_key is not configured, login is disabled.");
+        }
+        let email = match &config.email {
+            Some(email) => EmailService::new(email)?,
+            None => {
+                tracing::warn!("email is not configured, emails will stay in the outbox until it is configured.");
+                EmailService::with_transport(Arc::new(DisabledTransport), DEFAULT_MAIL_FROM, None)?
+            }
+        };
         // config
         Ok(Context {
+            reloader: Arc::new(ConfigReloader::new(config.clone())),
             config,
             version: Arc::new(Version::default()),
-            db: db.into(),
-            cluster_event: Arc::new(sender),
+            db,
+            cluster_event: sender,
+            metrics: Arc::new(metrics),
+            jwt,
+            jwt_issuer,
+            email: Arc::new(email),
+            health: Arc::new(HealthChecks::default()),
+            permissions: Arc::new(PermissionCache::default()),
+            logging: None,
         })
     }
 
+    /// 配置变化时同步修改日志等级，并开放日志等级管理接口
+    pub(crate) fn with_logging(mut self, handle: LoggingHandle) -> Self {
+        self.reloader.apply_logging(handle.clone());
+        self.logging = Some(handle);
+        self
+    }
+
     pub(crate) async fn add_cluster_event_hook(&self) {
         let c = self.cluster_event.clone();
         shutdown::push(async move {
@@ -41,17 +98,48 @@ impl Context {
         .await;
     }
 
+    /// 按`data_source.migration`执行迁移，多个实例同时启动时只有一个实例执行
     pub async fn run_database_migration(&self) -> AppResult<()> {
-        if env::enable_migration() {
-            let db = &self.db;
-            migration::migrations(db).await?;
-        }
+        let source = &self.config.data_source;
+        let target = match &source.migration {
+            MigrationPolicy::Off | MigrationPolicy::VerifyOnly => return Ok(()),
+            MigrationPolicy::Up => None,
+            MigrationPolicy::UpTo(name) => Some(name.as_str()),
+        };
+        migration::migrate(&self.db, &source.url, target, source.migration_lock_timeout).await?;
         Ok(())
     }
+
+    /// 检查数据库与程序的迁移是否一致，按`data_source.drift_check`拒绝启动或警告，`verify-only`时总是拒绝启动
+    pub async fn check_schema_drift(&self) -> AppResult<()> {
+        match self.schema_drift().await? {
+            Some((DriftCheck::Refuse, drift)) => Err(AppError::SchemaDrift(drift)),
+            Some((_, drift)) => {
+                tracing::warn!("{}", drift);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// 数据库与程序的迁移差异及处理方式，启动及就绪检查共用：
+    /// `verify-only`时总是拒绝，`up-to`时目标之后未执行的迁移不算差异，`drift_check`为`off`时不检查
+    pub(crate) async fn schema_drift(&self) -> AppResult<Option<(DriftCheck, SchemaDrift)>> {
+        let source = &self.config.data_source;
+        let (check, target) = match &source.migration {
+            MigrationPolicy::VerifyOnly => (DriftCheck::Refuse, None),
+            MigrationPolicy::UpTo(name) => (source.drift_check, Some(name.as_str())),
+            _ => (source.drift_check, None),
+        };
+        if check == DriftCheck::Off {
+            return Ok(None);
+        }
+        Ok(migration::report(&self.db).await?.drift_until(target).map(|drift| (check, drift)))
+    }
 }
 
 /// 初始化连接池各项配置
-fn init_pool_opt(source: &DataSource) -> sea_orm::ConnectOptions {
+pub(crate) fn init_pool_opt(source: &DataSource) -> sea_orm::ConnectOptions {
     let mut opt = sea_orm::ConnectOptions::new(&source.url);
     if let Some(max_connections) = source.max_connections {
         opt.max_connections(max_connections);
@@ -1,3 +1,5 @@
+use crate::configs::ConfigErrors;
+use salvo::http::StatusCode;
 use sea_orm::DbErr;
 use thiserror::Error;
 use tracing_subscriber::filter::LevelParseError;
@@ -18,6 +20,9 @@ pub enum AppError {
     #[error("{0}")]
     Config(#[from] config::ConfigError),
 
+    #[error("invalid configuration:\n{0}")]
+    ConfigInvalid(ConfigErrors),
+
     #[error("{0}")]
     Db(#[from] DbErr),
 
@@ -27,5 +32,59 @@ pub enum AppError {
     #[error("{0}")]
     LevelParse(#[from] LevelParseError),
 
+    #[error("{0}")]
+    Logging(String),
+
+    #[error("{0}")]
+    Metrics(#[from] prometheus::Error),
+
+    #[error("{0}")]
+    Jwt(#[from] jsonwebtoken::errors::Error),
+
+    #[error("{0}")]
+    PasswordHash(#[from] argon2::password_hash::Error),
+
+    #[error("{0}")]
+    Email(#[from] common::email::EmailError),
+
+    #[error("{0}")]
+    SchemaDrift(common::migration::SchemaDrift),
+
+    #[error("invalid seed data: {0}")]
+    Seed(String),
+
+    /// 命令行参数不满足执行条件
+    #[error("{0}")]
+    Cli(String),
+
+    /// 请求参数不合法
+    #[error("{0}")]
+    BadRequest(String),
+
+    /// 与已有数据冲突，eg:用户名已存在
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error("{0}")]
+    Unauthorized(&'static str),
+
+    #[error("{0}")]
+    Forbidden(String),
+
+    #[error("{0}")]
+    NotFound(String),
+}
 
-}
\ No newline at end of file
+impl AppError {
+    /// 返回给调用方的错误码
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
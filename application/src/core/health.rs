@@ -0,0 +1,170 @@
+//! 健康检查：`/health/live`只表示进程存活，`/health/ready`汇总所有已注册的检查项，
+//! 任意一项失败即不可接收流量。业务可通过[`Context::health`]注册自己的检查项。
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::configs::DriftCheck;
+use crate::core::context::Context;
+use crate::core::shutdown;
+
+/// 单个检查项的超时时间，超时视为失败
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[async_trait::async_trait]
+pub trait HealthCheck: Send + Sync {
+    /// 检查项名称，同名的检查项后注册的覆盖先注册的
+    fn name(&self) -> &str;
+
+    /// 失败时返回原因
+    async fn check(&self, ctx: &Context) -> Result<(), String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: BTreeMap<String, CheckResult>,
+}
+
+impl HealthReport {
+    pub fn is_up(&self) -> bool {
+        self.status == HealthStatus::Up
+    }
+}
+
+/// 已注册的检查项
+pub struct HealthChecks {
+    checks: RwLock<Vec<Arc<dyn HealthCheck>>>,
+}
+
+impl Default for HealthChecks {
+    /// 内置：关闭中、数据库、数据库迁移、集群事件
+    fn default() -> Self {
+        let checks = Self::empty();
+        checks.register(ShutdownCheck);
+        checks.register(DatabaseCheck);
+        checks.register(MigrationCheck);
+        checks.register(EventBusCheck);
+        checks
+    }
+}
+
+impl HealthChecks {
+    pub fn empty() -> Self {
+        Self { checks: RwLock::new(Vec::new()) }
+    }
+
+    pub fn register<C: HealthCheck + 'static>(&self, check: C) {
+        let mut checks = self.checks.write().unwrap_or_else(|e| e.into_inner());
+        checks.retain(|c| c.name() != check.name());
+        checks.push(Arc::new(check));
+    }
+
+    /// 并发执行所有检查项
+    pub async fn run(&self, ctx: &Context) -> HealthReport {
+        let checks = self.checks.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let results = futures::future::join_all(checks.iter().map(|c| async move {
+            let result = match tokio::time::timeout(CHECK_TIMEOUT, c.check(ctx)).await {
+                Ok(r) => r,
+                Err(_) => Err(format!("timeout after {:?}", CHECK_TIMEOUT)),
+            };
+            (c.name().to_string(), result)
+        }))
+        .await;
+
+        let mut status = HealthStatus::Up;
+        let mut report = BTreeMap::new();
+        for (name, result) in results {
+            let result = match result {
+                Ok(()) => CheckResult { status: HealthStatus::Up, error: None },
+                Err(e) => {
+                    tracing::warn!("health check {} failed: {}", name, e);
+                    status = HealthStatus::Down;
+                    CheckResult { status: HealthStatus::Down, error: Some(e) }
+                }
+            };
+            report.insert(name, result);
+        }
+        HealthReport { status, checks: report }
+    }
+}
+
+/// 收到退出信号后立即不再接收流量
+struct ShutdownCheck;
+
+#[async_trait::async_trait]
+impl HealthCheck for ShutdownCheck {
+    fn name(&self) -> &str {
+        "shutdown"
+    }
+
+    async fn check(&self, _ctx: &Context) -> Result<(), String> {
+        if shutdown::is_shutting_down() {
+            return Err("application is shutting down".to_string());
+        }
+        Ok(())
+    }
+}
+
+struct DatabaseCheck;
+
+#[async_trait::async_trait]
+impl HealthCheck for DatabaseCheck {
+    fn name(&self) -> &str {
+        "database"
+    }
+
+    async fn check(&self, ctx: &Context) -> Result<(), String> {
+        ctx.db.ping().await.map_err(|e| e.to_string())
+    }
+}
+
+/// 表结构与代码不一致，且`data_source.drift_check`为`refuse`或`migration`为`verify-only`时不可接收流量
+struct MigrationCheck;
+
+#[async_trait::async_trait]
+impl HealthCheck for MigrationCheck {
+    fn name(&self) -> &str {
+        "migration"
+    }
+
+    async fn check(&self, ctx: &Context) -> Result<(), String> {
+        match ctx.schema_drift().await.map_err(|e| e.to_string())? {
+            Some((DriftCheck::Refuse, drift)) => Err(drift.to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+struct EventBusCheck;
+
+#[async_trait::async_trait]
+impl HealthCheck for EventBusCheck {
+    fn name(&self) -> &str {
+        "cluster_event"
+    }
+
+    async fn check(&self, ctx: &Context) -> Result<(), String> {
+        if ctx.cluster_event.is_stopped() {
+            return Err("cluster event bus has been stopped".to_string());
+        }
+        Ok(())
+    }
+}
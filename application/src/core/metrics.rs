@@ -0,0 +1,144 @@
+//! Prometheus指标：HTTP请求、数据库连接池及集群事件
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::queue::cluster_event::ClusterEventSender;
+use prometheus::core::{Collector, Desc};
+use prometheus::proto::{self, MetricFamily, MetricType};
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder};
+use sea_orm::DatabaseConnection;
+
+use crate::core::errors::AppResult;
+
+const HTTP_LABELS: [&str; 3] = ["method", "route", "status"];
+
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+}
+
+impl Metrics {
+    pub fn new(db: Arc<DatabaseConnection>, cluster_event: Arc<ClusterEventSender>) -> AppResult<Self> {
+        let registry = Registry::new();
+        let http_requests = IntCounterVec::new(Opts::new("http_requests_total", "Total number of HTTP requests."), &HTTP_LABELS)?;
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency in seconds."),
+            &HTTP_LABELS,
+        )?;
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_duration.clone()))?;
+        registry.register(Box::new(StateCollector::new(db, cluster_event)?))?;
+        Ok(Self {
+            registry,
+            http_requests,
+            http_duration,
+        })
+    }
+
+    /// 记录一次HTTP请求
+    pub fn observe_http(&self, method: &str, route: &str, status: u16, duration: Duration) {
+        let status = status.to_string();
+        let labels = [method, route, status.as_str()];
+        self.http_requests.with_label_values(&labels).inc();
+        self.http_duration.with_label_values(&labels).observe(duration.as_secs_f64());
+    }
+
+    /// Prometheus文本格式的所有指标
+    pub fn render(&self) -> AppResult<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+}
+
+/// 采集时读取连接池及集群事件的状态
+struct StateCollector {
+    db: Arc<DatabaseConnection>,
+    cluster_event: Arc<ClusterEventSender>,
+    pool_connections: IntGaugeVec,
+    pool_max_connections: IntGauge,
+    events_sent: Desc,
+    events_lagged: Desc,
+    events_dropped: Desc,
+}
+
+impl StateCollector {
+    fn new(db: Arc<DatabaseConnection>, cluster_event: Arc<ClusterEventSender>) -> AppResult<Self> {
+        Ok(Self {
+            db,
+            cluster_event,
+            pool_connections: IntGaugeVec::new(Opts::new("db_pool_connections", "Database pool connections by state."), &["state"])?,
+            pool_max_connections: IntGauge::new("db_pool_max_connections", "Maximum connections of the database pool.")?,
+            events_sent: counter_desc("cluster_events_sent_total", "Cluster events sent.")?,
+            events_lagged: counter_desc("cluster_events_lagged_total", "Cluster events missed by lagged receivers.")?,
+            events_dropped: counter_desc("cluster_events_dropped_total", "Cluster events failed to send.")?,
+        })
+    }
+
+    fn update(&self) {
+        if let Some((size, idle, max)) = pool_state(&self.db) {
+            self.pool_connections.with_label_values(&["in_use"]).set((size as i64 - idle as i64).max(0));
+            self.pool_connections.with_label_values(&["idle"]).set(idle as i64);
+            self.pool_max_connections.set(max as i64);
+        }
+    }
+
+    /// 集群事件的计数直接取自[`ClusterEventSender`]的累计值，不在采集时累加，并发采集互不影响
+    fn events(&self) -> Vec<MetricFamily> {
+        let stats = self.cluster_event.stats();
+        [(&self.events_sent, stats.sent()), (&self.events_lagged, stats.lagged()), (&self.events_dropped, stats.dropped())]
+            .into_iter()
+            .map(|(desc, value)| counter_family(desc, value))
+            .collect()
+    }
+}
+
+fn counter_desc(name: &str, help: &str) -> AppResult<Desc> {
+    Ok(Desc::new(name.to_string(), help.to_string(), vec![], Default::default())?)
+}
+
+fn counter_family(desc: &Desc, value: u64) -> MetricFamily {
+    let mut counter = proto::Counter::default();
+    counter.set_value(value as f64);
+    let mut metric = proto::Metric::default();
+    metric.set_counter(counter);
+    let mut family = MetricFamily::default();
+    family.set_name(desc.fq_name.clone());
+    family.set_help(desc.help.clone());
+    family.set_field_type(MetricType::COUNTER);
+    family.set_metric(vec![metric]);
+    family
+}
+
+impl Collector for StateCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        let mut desc = self.pool_connections.desc();
+        desc.extend(self.pool_max_connections.desc());
+        desc.extend([&self.events_sent, &self.events_lagged, &self.events_dropped]);
+        desc
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        self.update();
+        let mut families = self.pool_connections.collect();
+        families.extend(self.pool_max_connections.collect());
+        families.extend(self.events());
+        families
+    }
+}
+
+/// 连接池的连接数、空闲连接数及最大连接数，sqlx不提供等待获取连接的数量
+fn pool_state(db: &DatabaseConnection) -> Option<(u32, usize, u32)> {
+    match db {
+        DatabaseConnection::SqlxMySqlPoolConnection(_) => {
+            let pool = db.get_mysql_connection_pool();
+            Some((pool.size(), pool.num_idle(), pool.options().get_max_connections()))
+        }
+        DatabaseConnection::SqlxSqlitePoolConnection(_) => {
+            let pool = db.get_sqlite_connection_pool();
+            Some((pool.size(), pool.num_idle(), pool.options().get_max_connections()))
+        }
+        _ => None,
+    }
+}
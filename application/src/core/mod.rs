@@ -1,6 +1,10 @@
 pub(crate) mod context;
 pub(crate) mod errors;
+pub mod health;
+pub(crate) mod metrics;
+pub(crate) mod reload;
 pub mod salvo;
+pub(crate) mod secret;
 pub(crate) mod shutdown;
 pub mod version;
 pub mod rapidoc;
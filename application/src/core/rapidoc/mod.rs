@@ -82,12 +82,14 @@ impl RapiDoc {
     }
 
     /// Set keywords of the html page.
+    #[allow(dead_code)]
     pub fn keywords(mut self, keywords: impl Into<Cow<'static, str>>) -> Self {
         self.keywords = Some(keywords.into());
         self
     }
 
     /// Set description of the html page.
+    #[allow(dead_code)]
     pub fn description(mut self, description: impl Into<Cow<'static, str>>) -> Self {
         self.description = Some(description.into());
         self
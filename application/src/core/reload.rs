@@ -0,0 +1,207 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::watch;
+
+use crate::configs::{AppConfig, ConfigSource, OpenApi};
+use crate::core::errors::AppResult;
+use crate::logging::{Logging, LoggingHandle};
+
+/// 检查外部配置文件是否变化的间隔
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 配置热加载，只有日志等级、cors、文档开关、管理接口令牌、密码策略及账号配置可以在运行期间修改，其他配置项需要重启生效
+pub struct ConfigReloader {
+    tx: watch::Sender<Arc<AppConfig>>,
+}
+
+impl ConfigReloader {
+    pub fn new(config: Arc<AppConfig>) -> Self {
+        let (tx, _) = watch::channel(config);
+        Self { tx }
+    }
+
+    /// 当前生效的配置
+    pub fn current(&self) -> Arc<AppConfig> {
+        self.tx.borrow().clone()
+    }
+
+    /// 订阅配置变更通知
+    pub fn subscribe(&self) -> watch::Receiver<Arc<AppConfig>> {
+        self.tx.subscribe()
+    }
+
+    /// 应用新的配置，不可热加载的配置项保持原值并告警，返回配置是否发生了变化
+    pub fn reload(&self, config: AppConfig) -> AppResult<bool> {
+        config.validate()?;
+        let current = self.current();
+        let (config, rejected) = merge_reloadable(&current, config);
+        for key in rejected {
+            tracing::warn!("configuration `{}` can not be reloaded, restart the application to apply it.", key);
+        }
+        if config == *current {
+            return Ok(false);
+        }
+        tracing::info!("configuration reloaded.");
+        self.tx.send_replace(Arc::new(config));
+        Ok(true)
+    }
+
+    /// 监听外部配置文件变化及`SIGHUP`信号，重新加载配置
+    pub fn watch(self: Arc<Self>, source: ConfigSource) {
+        tokio::spawn(async move {
+            let files = source.files();
+            let mut modified = modified_times(&files);
+            let mut interval = tokio::time::interval(WATCH_INTERVAL);
+            let mut hangup = Hangup::new();
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        let latest = modified_times(&files);
+                        if latest == modified {
+                            continue;
+                        }
+                        modified = latest;
+                        tracing::info!("configuration file changed, reloading.");
+                    }
+                    _ = hangup.recv() => tracing::info!("received SIGHUP, reloading configuration."),
+                }
+                if let Err(e) = source.load().and_then(|config| self.reload(config)) {
+                    tracing::warn!("reload configuration fail, keep the current configuration: {}", e);
+                }
+            }
+        });
+    }
+
+    /// 配置变化时同步修改日志等级
+    pub fn apply_logging(&self, handle: LoggingHandle) {
+        let mut rx = self.subscribe();
+        tokio::spawn(async move {
+            while rx.changed().await.is_ok() {
+                let config = rx.borrow_and_update().clone();
+                if let Err(e) = handle.update(&config.logging) {
+                    tracing::warn!("apply logging configuration fail: {}", e);
+                }
+            }
+        });
+    }
+}
+
+/// 合并可热加载的配置项，返回合并后的配置及被拒绝的配置项
+fn merge_reloadable(current: &AppConfig, new: AppConfig) -> (AppConfig, Vec<&'static str>) {
+    let mut rejected = Vec::new();
+    if new.server != current.server {
+        rejected.push("server");
+    }
+    if new.data_source != current.data_source {
+        rejected.push("data_source");
+    }
+    if new.openapi.server != current.openapi.server {
+        rejected.push("openapi.server");
+    }
+    if new.jwt != current.jwt {
+        rejected.push("jwt");
+    }
+    if new.email != current.email {
+        rejected.push("email");
+    }
+    // 日志只有等级可以热加载
+    let logging = Logging {
+        level: new.logging.level.clone(),
+        level_list: new.logging.level_list.clone(),
+        ..current.logging.clone()
+    };
+    if logging != new.logging {
+        rejected.push("logging (except level, level_list)");
+    }
+
+    let config = AppConfig {
+        server: current.server.clone(),
+        data_source: current.data_source.clone(),
+        openapi: OpenApi {
+            server: current.openapi.server.clone(),
+            ..new.openapi
+        },
+        logging,
+        admin: new.admin,
+        metrics: new.metrics,
+        jwt: current.jwt.clone(),
+        password: new.password,
+        account: new.account,
+        email: current.email.clone(),
+        email_outbox: new.email_outbox,
+    };
+    (config, rejected)
+}
+
+fn modified_times(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files.iter().map(|f| f.metadata().and_then(|m| m.modified()).ok()).collect()
+}
+
+/// `SIGHUP`信号，非unix系统下永远不会触发
+struct Hangup {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl Hangup {
+    fn new() -> Self {
+        #[cfg(unix)]
+        {
+            let signal = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+                .inspect_err(|e| tracing::warn!("can not listen SIGHUP: {}", e))
+                .ok();
+            Self { signal }
+        }
+        #[cfg(not(unix))]
+        Self {}
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = self.signal.as_mut() {
+            signal.recv().await;
+            return;
+        }
+        std::future::pending::<()>().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> AppConfig {
+        crate::configs::tests::load("/not/exists/application.yaml", None, &[("APP__DATA_SOURCE__URL", "sqlite::memory:")])
+    }
+
+    #[tokio::test]
+    async fn test_reload() {
+        let reloader = ConfigReloader::new(Arc::new(config()));
+        let mut rx = reloader.subscribe();
+
+        let mut new = config();
+        new.server.port = 9999;
+        new.logging.level = "warn".into();
+        new.logging.level_list = Some(vec!["sqlx=debug".into()]);
+        new.openapi.cors_origin = vec!["http://a.com".into()];
+        new.openapi.enable_web_doc = false;
+        assert!(reloader.reload(new).unwrap());
+        assert!(rx.has_changed().unwrap());
+
+        let current = reloader.current();
+        assert_eq!(current.server.port, 8080);
+        assert_eq!(current.logging.level, "warn");
+        assert_eq!(current.logging.level_list, Some(vec!["sqlx=debug".to_string()]));
+        assert_eq!(current.openapi.cors_origin, vec!["http://a.com"]);
+        assert!(!current.openapi.enable_web_doc);
+
+        rx.borrow_and_update();
+        let mut invalid = config();
+        invalid.logging.level = "loud".into();
+        assert!(reloader.reload(invalid).is_err());
+        assert!(!reloader.reload((*current).clone()).unwrap());
+        assert!(!rx.has_changed().unwrap());
+    }
+}
@@ -0,0 +1,30 @@
+use salvo::http::StatusCode;
+use salvo::{Depot, FlowCtrl, Handler, Request, Response, Writer};
+
+use crate::auth::constant_time_eq;
+use crate::core::errors::AppError;
+use crate::core::salvo::auth::bearer_token;
+use crate::core::salvo::context_inject::require_context;
+
+/// 管理接口认证：`Authorization: Bearer {admin.token}`，未配置令牌时返回404
+#[derive(Clone, Copy, Debug)]
+pub struct AdminGuard;
+
+#[async_trait::async_trait]
+impl Handler for AdminGuard {
+    async fn handle(&self, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+        let Some(ctx) = require_context(req, depot, res, ctrl).await else {
+            return;
+        };
+        let config = ctx.reloader.current();
+        let Some(token) = config.admin.token.as_deref() else {
+            res.status_code(StatusCode::NOT_FOUND);
+            ctrl.skip_rest();
+            return;
+        };
+        if !bearer_token(req).is_some_and(|bearer| constant_time_eq(bearer.as_bytes(), token.as_bytes())) {
+            AppError::Unauthorized("invalid admin token").write(req, depot, res).await;
+            ctrl.skip_rest();
+        }
+    }
+}
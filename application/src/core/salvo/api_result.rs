@@ -24,10 +24,22 @@ pub struct ResponseResult<'a, T> {
     pub data: Option<T>,
 }
 
+/// 分页数据
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct Page<T: ToSchema + 'static> {
+    pub items: Vec<T>,
+    /// 总数
+    pub total: u64,
+    /// 页码，从1开始
+    pub page: u64,
+    /// 每页数量
+    pub size: u64,
+}
+
 impl<'a> ResponseResult<'_, bool> {
-    pub fn err(trace_id: &'a str, message: &'a str) -> ResponseResult<'a, bool> {
+    pub fn err(code: &'a StatusCode, trace_id: &'a str, message: &'a str) -> ResponseResult<'a, bool> {
         ResponseResult {
-            code: StatusCode::INTERNAL_SERVER_ERROR.as_str(),
+            code: code.as_str(),
             message,
             trace_id: Some(trace_id),
             data: None,
@@ -0,0 +1,279 @@
+use std::sync::Arc;
+
+use salvo::http::header::AUTHORIZATION;
+use salvo::oapi::security::SecurityRequirement;
+use salvo::oapi::RouterExt;
+use salvo::{Depot, FlowCtrl, Handler, Request, Response, Router, Writer};
+
+use crate::auth::Principal;
+use crate::core::errors::{AppError, AppResult};
+use crate::core::salvo::context_inject::{insert_arc, obtain_arc, require_context};
+use crate::core::salvo::{HEADER_APP_TOKEN, TRACE_USER_OR_APP_NAME};
+use crate::service::app_key_service::AppKeyService;
+use crate::service::permission_service::PermissionService;
+
+/// JWT认证：`Authorization: Bearer {jwt}`，通过后放入[`Principal`]，失败时返回401
+#[derive(Clone, Copy, Debug)]
+pub struct JwtAuth;
+
+#[async_trait::async_trait]
+impl Handler for JwtAuth {
+    async fn handle(&self, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+        let Some(ctx) = require_context(req, depot, res, ctrl).await else {
+            return;
+        };
+        let result = match ctx.jwt.as_deref() {
+            Some(verifier) => bearer_token(req).ok_or(AppError::Unauthorized("missing bearer token")).and_then(|token| verifier.verify(token)),
+            None => Err(AppError::Unauthorized("authentication is not configured")),
+        };
+        authenticated(result.map(Principal::from), req, depot, res, ctrl).await;
+    }
+}
+
+/// 外部应用认证：请求头`APP-TOKEN: {key}`，通过后放入[`Principal`]，失败时返回401
+#[derive(Clone, Copy, Debug)]
+pub struct AppTokenAuth;
+
+#[async_trait::async_trait]
+impl Handler for AppTokenAuth {
+    async fn handle(&self, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+        let Some(ctx) = require_context(req, depot, res, ctrl).await else {
+            return;
+        };
+        let token = req.headers().get(HEADER_APP_TOKEN).and_then(|v| v.to_str().ok()).map(str::trim).filter(|t| !t.is_empty());
+        let result = match token {
+            Some(token) => AppKeyService::authenticate(&ctx, token).await,
+            None => Err(AppError::Unauthorized("missing app token")),
+        };
+        authenticated(result, req, depot, res, ctrl).await;
+    }
+}
+
+/// 认证通过时记录调用方，失败时返回错误并终止请求
+async fn authenticated(result: AppResult<Principal>, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+    match result {
+        Ok(principal) => {
+            depot.insert(TRACE_USER_OR_APP_NAME, principal.name.clone());
+            insert_arc(depot, Arc::new(principal));
+        }
+        Err(e) => {
+            e.write(req, depot, res).await;
+            ctrl.skip_rest();
+        }
+    }
+}
+
+/// 检查调用方是否拥有接口声明的所有权限，缺少时返回403，需要在认证之后执行
+#[derive(Clone, Debug)]
+pub struct RequirePermission {
+    pub permissions: Vec<String>,
+}
+
+#[async_trait::async_trait]
+impl Handler for RequirePermission {
+    async fn handle(&self, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+        let Some(ctx) = require_context(req, depot, res, ctrl).await else {
+            return;
+        };
+        let result = match obtain_principal(depot) {
+            Ok(principal) => PermissionService::granted(&ctx, principal).await,
+            Err(_) => Err(AppError::Unauthorized("unauthenticated")),
+        };
+        let missing = result.map(|granted| self.permissions.iter().filter(|p| !granted.contains(*p)).cloned().collect::<Vec<_>>());
+        let error = match missing {
+            Ok(missing) if missing.is_empty() => return,
+            Ok(missing) => AppError::Forbidden(format!("missing permission: {}", missing.join(", "))),
+            Err(e) => e,
+        };
+        error.write(req, depot, res).await;
+        ctrl.skip_rest();
+    }
+}
+
+/// 路由下的接口需要`permissions`中的所有权限：添加[`RequirePermission`]，并在开放文档中声明，
+/// `scheme`为认证方式，eg:`permit(Router::new().get(list), SECURITY_WEB, &[PERMISSION_USER_READ])`
+pub fn permit(router: Router, scheme: &str, permissions: &[&str]) -> Router {
+    router
+        .oapi_security(SecurityRequirement::new(scheme, permissions.iter().copied()))
+        .hoop(RequirePermission { permissions: permissions.iter().map(|p| p.to_string()).collect() })
+}
+
+/// 请求头`Authorization: Bearer {token}`中的令牌
+pub fn bearer_token(req: &Request) -> Option<&str> {
+    let value = req.headers().get(AUTHORIZATION)?.to_str().ok()?;
+    let token = value.strip_prefix("Bearer ")?.trim();
+    (!token.is_empty()).then_some(token)
+}
+
+/// 当前请求已认证的调用方
+pub fn obtain_principal(depot: &Depot) -> AppResult<&Arc<Principal>> {
+    obtain_arc::<Principal>(depot)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use salvo::http::StatusCode;
+    use salvo::test::{ResponseExt, TestClient};
+    use salvo::{handler, Router, Service};
+    use serde_json::Value;
+
+    use super::*;
+    use crate::auth::jwt::tests::{claims, hs256, sign};
+    use crate::core::context::Context;
+    use crate::core::salvo::context_inject::ContextInject;
+    use crate::service::app_key_service::CreateAppKey;
+    use crate::service::user_service::UserService;
+    use crate::core::salvo::SECURITY_WEB;
+    use crate::test_support::TestApp;
+    use common::domain::user::{role_permission, UserRepository};
+    use salvo::oapi::{endpoint, OpenApi};
+    use sea_orm::sqlx::types::chrono::Utc;
+    use sea_orm::EntityTrait;
+    use std::time::Duration;
+
+    #[handler]
+    async fn me(depot: &mut Depot) -> AppResult<String> {
+        let principal = obtain_principal(depot)?;
+        let name = depot.get::<String>(TRACE_USER_OR_APP_NAME).map_err(|_| AppError::ApiRequestParamStr("missing name"))?;
+        Ok(format!("{}:{}", principal.id, name))
+    }
+
+    async fn auth_service(jwt: bool) -> Service {
+        let mut config = crate::configs::tests::load("/not/exists/application.yaml", None, &[("APP__DATA_SOURCE__URL", "sqlite::memory:")]);
+        config.jwt = jwt.then(hs256);
+        let ctx = Arc::new(Context::new(config).await.unwrap());
+        Service::new(Router::with_path("me").hoop(JwtAuth).get(me)).hoop(ContextInject { context: ctx })
+    }
+
+    #[handler]
+    async fn app_principal(depot: &mut Depot) -> AppResult<String> {
+        let principal = obtain_principal(depot)?;
+        Ok(format!("{:?}:{}:{}", principal.kind, principal.name, principal.scopes.join(",")))
+    }
+
+    /// 查询用户
+    #[endpoint]
+    async fn read_user() -> &'static str {
+        "user"
+    }
+
+    async fn get(service: &Service, token: Option<&str>) -> (StatusCode, String) {
+        let mut client = TestClient::get("http://127.0.0.1:5801/me");
+        if let Some(token) = token {
+            client = client.bearer_auth(token);
+        }
+        let mut res = client.send(service).await;
+        (res.status_code.unwrap_or(StatusCode::OK), res.take_string().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_jwt_auth() {
+        let service = auth_service(true).await;
+        assert_eq!(get(&service, Some(&sign(&claims("7", 60)))).await, (StatusCode::OK, "7:tester".to_string()));
+
+        for (token, message) in [(None, "missing bearer token"), (Some(sign(&claims("7", -10))), "token expired"), (Some("abc".to_string()), "invalid token")] {
+            let (code, body) = get(&service, token.as_deref()).await;
+            assert_eq!(code, StatusCode::UNAUTHORIZED);
+            let body: Value = serde_json::from_str(&body).unwrap();
+            assert_eq!(body["code"], "401");
+            assert_eq!(body["message"], message);
+        }
+
+        // 未配置时拒绝所有请求
+        let unconfigured = auth_service(false).await;
+        let (code, body) = get(&unconfigured, Some(&sign(&claims("7", 60)))).await;
+        assert_eq!(code, StatusCode::UNAUTHORIZED);
+        assert!(body.contains("authentication is not configured"));
+
+        // 缺少上下文时不放行
+        let uninjected = Service::new(Router::with_path("me").hoop(JwtAuth).get(me));
+        let (_, body) = get(&uninjected, Some(&sign(&claims("7", 60)))).await;
+        assert!(body.contains(r#""code":"500""#) && !body.contains("tester"));
+    }
+
+    #[tokio::test]
+    async fn test_app_token_auth() {
+        let app = TestApp::new().await;
+        let ctx = app.ctx.clone();
+        let service = Service::new(Router::with_path("open").hoop(AppTokenAuth).get(app_principal)).hoop(ContextInject { context: ctx.clone() });
+        let call = |token: Option<String>| {
+            let service = &service;
+            async move {
+                let mut client = TestClient::get("http://127.0.0.1:5801/open");
+                if let Some(token) = token {
+                    client = client.add_header(HEADER_APP_TOKEN, token, true);
+                }
+                let mut res = client.send(service).await;
+                (res.status_code.unwrap_or(StatusCode::OK), res.take_string().await.unwrap())
+            }
+        };
+        let create = |expires_at| {
+            let ctx = ctx.clone();
+            async move {
+                let req = CreateAppKey { app_name: "partner".to_string(), scopes: vec!["read:items".to_string(), "edit:items".to_string()], expires_at };
+                AppKeyService::create(&ctx, req).await
+            }
+        };
+
+        let created = create(None).await.unwrap();
+        assert_eq!(created.key.len(), 64);
+        assert_eq!(created.app_key.key_prefix, &created.key[..8]);
+        assert_eq!(call(Some(created.key.clone())).await, (StatusCode::OK, "App:partner:read:items,edit:items".to_string()));
+
+        AppKeyService::revoke(&ctx, created.app_key.id).await.unwrap();
+        let revoked = AppKeyService::revoke(&ctx, 0).await;
+        assert!(revoked.is_err_and(|e| e.status_code() == StatusCode::NOT_FOUND));
+        let (code, body) = call(Some(created.key)).await;
+        assert_eq!(code, StatusCode::UNAUTHORIZED);
+        assert!(body.contains("app token revoked"));
+
+        assert!(create(Some(Utc::now() - Duration::from_secs(1))).await.is_err_and(|e| e.status_code() == StatusCode::BAD_REQUEST));
+        let expiring = create(Some(Utc::now() + Duration::from_millis(200))).await.unwrap();
+        assert_eq!(call(Some(expiring.key.clone())).await.0, StatusCode::OK);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(call(Some(expiring.key)).await.1.contains("app token expired"));
+
+        assert!(call(Some("0".repeat(64))).await.1.contains("invalid app token"));
+        assert!(call(None).await.1.contains("missing app token"));
+    }
+
+    #[tokio::test]
+    async fn test_authorize() {
+        let app = TestApp::new().await.seed("test").await;
+        let ctx = app.ctx.clone();
+        let admin = UserRepository::find_by_username(&ctx.db, "admin").await.unwrap().unwrap().id;
+        let viewer = UserRepository::find_by_username(&ctx.db, "viewer").await.unwrap().unwrap().id;
+        let admin_roles = UserService::find_by_id(&ctx, admin).await.unwrap().role_ids;
+
+        let users = permit(Router::with_path("users").get(read_user), SECURITY_WEB, &["user:write"]);
+        let router = Router::new().hoop(JwtAuth).push(users).push(Router::with_path("me").get(me));
+        let doc = serde_json::to_value(OpenApi::new("test", "1").merge_router(&router)).unwrap();
+        assert_eq!(doc["paths"]["/users"]["get"]["security"], serde_json::json!([{"Qbee": ["user:write"]}]));
+        let service = Service::new(router).hoop(ContextInject { context: ctx.clone() });
+        let call = |path: &'static str, user_id: i64| {
+            let service = &service;
+            let token = app.token(user_id);
+            async move {
+                let mut res = TestClient::get(format!("http://127.0.0.1:5801/{}", path)).bearer_auth(token).send(service).await;
+                (res.status_code.unwrap_or(StatusCode::OK), res.take_string().await.unwrap())
+            }
+        };
+
+        assert_eq!(call("users", admin).await, (StatusCode::OK, "user".to_string()));
+        let (code, body) = call("users", viewer).await;
+        assert_eq!(code, StatusCode::FORBIDDEN);
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["code"], "403");
+        assert_eq!(body["message"], "missing permission: user:write");
+        // 未声明权限的接口只需要认证
+        assert_eq!(call("me", viewer).await.0, StatusCode::OK);
+
+        // 权限已缓存，修改角色后清除
+        role_permission::Entity::delete_many().exec(ctx.db.as_ref()).await.unwrap();
+        assert_eq!(call("users", admin).await.0, StatusCode::OK);
+        UserService::set_roles(&ctx, admin, admin_roles).await.unwrap();
+        assert_eq!(call("users", admin).await.0, StatusCode::FORBIDDEN);
+    }
+}
@@ -1,6 +1,6 @@
 use std::any::{Any, TypeId};
 use std::sync::Arc;
-use salvo::{Depot, handler};
+use salvo::{Depot, FlowCtrl, handler, Request, Response, Writer};
 use crate::core::context::Context;
 use crate::core::errors::{AppError, AppResult};
 
@@ -17,7 +17,7 @@ impl<T: Send + Sync + 'static> ContextInject<T> {
 
 /// 从上下文中获取Arc<T>信息
 pub fn insert_arc<T: Any + Send + Sync>(depot: &mut Depot, t: Arc<T>) {
-    depot.insert(&type_key::<Arc<T>>(), t);
+    depot.insert(type_key::<Arc<T>>(), t);
 }
 
 /// 从上下文中获取T信息
@@ -45,4 +45,17 @@ fn type_key<T: 'static>() -> String {
 
 pub fn obtain_context(depot: &Depot) -> AppResult<&Arc<Context>> {
     obtain_arc::<Context>(depot)
-}
\ No newline at end of file
+}
+
+/// 中间件中获取上下文，缺失时返回错误并终止请求，避免未经检查就放行
+pub async fn require_context(req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) -> Option<Arc<Context>> {
+    match obtain_context(depot) {
+        Ok(ctx) => Some(ctx.clone()),
+        Err(e) => {
+            tracing::error!("context is not injected, check `ContextInject` of the router");
+            e.write(req, depot, res).await;
+            ctrl.skip_rest();
+            None
+        }
+    }
+}
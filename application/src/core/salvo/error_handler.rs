@@ -5,7 +5,7 @@ use salvo::prelude::Text;
 use sea_orm::prelude::async_trait;
 
 use crate::core::errors::AppError;
-use crate::core::salvo::{DEFAULT_JSON, REQUEST_ID_NAME, schema_500, to_401_schema, TRACE_ID_DEFAULT};
+use crate::core::salvo::{DEFAULT_JSON, REQUEST_ID_NAME, schema_500, to_status_schema, TRACE_ID_DEFAULT};
 use crate::core::salvo::api_result::ResponseResult;
 
 /// 全局异常处理
@@ -22,7 +22,12 @@ fn render_error_json(error: AppError, depot: &mut Depot, res: &mut Response) {
         .get::<String>(REQUEST_ID_NAME)
         .ok()
         .map_or(TRACE_ID_DEFAULT, |id| id.as_str());
-    if let Ok(str) = ResponseResult::err(id, error.to_string().as_str()).to_string() {
+    // 除认证失败等明确的错误外，保持以前的格式：http状态码为200，错误码为500
+    let code = error.status_code();
+    if code != StatusCode::INTERNAL_SERVER_ERROR {
+        res.status_code(code);
+    }
+    if let Ok(str) = ResponseResult::err(&code, id, error.to_string().as_str()).to_string() {
         res.render(Text::Json(str));
     } else {
         res.render(Text::Json(DEFAULT_JSON));
@@ -41,9 +46,25 @@ impl ToSchema for AppError {
 
 impl EndpointOutRegister for AppError {
     fn register(components: &mut Components, operation: &mut Operation) {
+        operation.responses.insert(
+            StatusCode::BAD_REQUEST.as_str(),
+            oapi::Response::new("Bad Request").add_content("application/json", to_status_schema(components, StatusCode::BAD_REQUEST)),
+        );
         operation.responses.insert(
             StatusCode::UNAUTHORIZED.as_str(),
-            oapi::Response::new("Unauthorized").add_content("application/json", to_401_schema(components)),
+            oapi::Response::new("Unauthorized").add_content("application/json", to_status_schema(components, StatusCode::UNAUTHORIZED)),
+        );
+        operation.responses.insert(
+            StatusCode::FORBIDDEN.as_str(),
+            oapi::Response::new("Forbidden").add_content("application/json", to_status_schema(components, StatusCode::FORBIDDEN)),
+        );
+        operation.responses.insert(
+            StatusCode::NOT_FOUND.as_str(),
+            oapi::Response::new("Not Found").add_content("application/json", to_status_schema(components, StatusCode::NOT_FOUND)),
+        );
+        operation.responses.insert(
+            StatusCode::CONFLICT.as_str(),
+            oapi::Response::new("Conflict").add_content("application/json", to_status_schema(components, StatusCode::CONFLICT)),
         );
         operation.responses.insert(
             StatusCode::INTERNAL_SERVER_ERROR.as_str(),
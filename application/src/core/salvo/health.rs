@@ -0,0 +1,128 @@
+use salvo::http::StatusCode;
+use salvo::writing::Json;
+use salvo::{handler, Depot, Response, Router};
+
+use crate::core::errors::AppResult;
+use crate::core::health::HealthStatus;
+use crate::core::salvo::context_inject::obtain_context;
+
+/// `/health/live`及`/health/ready`
+pub fn router() -> Router {
+    Router::with_path("health").push(Router::with_path("live").get(live)).push(Router::with_path("ready").get(ready))
+}
+
+/// 存活检查，进程能响应即可
+#[handler]
+pub async fn live(res: &mut Response) {
+    res.render(Json(serde_json::json!({ "status": HealthStatus::Up })));
+}
+
+/// 就绪检查，任意检查项失败时返回503
+#[handler]
+pub async fn ready(depot: &mut Depot, res: &mut Response) -> AppResult<()> {
+    let ctx = obtain_context(depot)?;
+    let report = ctx.health.run(ctx).await;
+    if !report.is_up() {
+        res.status_code(StatusCode::SERVICE_UNAVAILABLE);
+    }
+    res.render(Json(report));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use salvo::test::{ResponseExt, TestClient};
+    use salvo::Service;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::configs::{DataSource, DriftCheck, MigrationPolicy};
+    use crate::core::context::Context;
+    use crate::core::health::HealthCheck;
+    use crate::core::salvo::context_inject::ContextInject;
+
+    struct Fixed(Result<(), String>);
+
+    #[async_trait::async_trait]
+    impl HealthCheck for Fixed {
+        fn name(&self) -> &str {
+            "migration"
+        }
+
+        async fn check(&self, _ctx: &Context) -> Result<(), String> {
+            self.0.clone()
+        }
+    }
+
+    async fn get(service: &Service, path: &str) -> (StatusCode, Value) {
+        let mut res = TestClient::get(format!("http://127.0.0.1:5801/health/{}", path)).send(service).await;
+        (res.status_code.unwrap(), res.take_json::<Value>().await.unwrap())
+    }
+
+    async fn context(f: impl FnOnce(&mut DataSource)) -> Arc<Context> {
+        let mut config = crate::configs::tests::load("/not/exists/application.yaml", None, &[("APP__DATA_SOURCE__URL", "sqlite::memory:")]);
+        f(&mut config.data_source);
+        Arc::new(Context::new(config).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_health() {
+        let ctx = context(|source| source.drift_check = DriftCheck::Refuse).await;
+        let service = Service::new(router()).hoop(ContextInject { context: ctx.clone() });
+        let get = |path| get(&service, path);
+
+        let (code, body) = get("live").await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(body["status"], "UP");
+
+        // 未执行迁移
+        let (code, body) = get("ready").await;
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "DOWN");
+        assert_eq!(body["checks"]["database"]["status"], "UP");
+        assert_eq!(body["checks"]["shutdown"]["status"], "UP");
+        assert_eq!(body["checks"]["migration"]["status"], "DOWN");
+
+        // 同名检查项覆盖内置的
+        ctx.health.register(Fixed(Ok(())));
+        let (code, body) = get("ready").await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(body["status"], "UP");
+
+        ctx.cluster_event.stop();
+        let (code, body) = get("ready").await;
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["checks"]["cluster_event"]["status"], "DOWN");
+        assert_eq!(body["checks"]["cluster_event"]["error"], "cluster event bus has been stopped");
+    }
+
+    #[tokio::test]
+    async fn test_migration_ready() {
+        let names = common::migration::names();
+        let migration = |ctx: Arc<Context>| async move {
+            let (code, body) = get(&Service::new(router()).hoop(ContextInject { context: ctx }), "ready").await;
+            (code, body["checks"]["migration"]["status"].as_str().unwrap().to_string())
+        };
+
+        // 默认只警告，未执行迁移也可以接收流量
+        let ctx = context(|_| {}).await;
+        assert_eq!(migration(ctx).await, (StatusCode::OK, "UP".to_string()));
+
+        // 只执行到指定的迁移，之后未执行的迁移不算差异
+        let target = names[0].clone();
+        let ctx = context(|source| {
+            source.migration = MigrationPolicy::UpTo(target.clone());
+            source.drift_check = DriftCheck::Refuse;
+        })
+        .await;
+        assert_eq!(migration(ctx.clone()).await.1, "DOWN");
+        common::migration::up_to(&ctx.db, Some(&target)).await.unwrap();
+        assert_eq!(migration(ctx).await, (StatusCode::OK, "UP".to_string()));
+
+        // `verify-only`时总是拒绝
+        let ctx = context(|source| source.migration = MigrationPolicy::VerifyOnly).await;
+        assert_eq!(migration(ctx).await.1, "DOWN");
+    }
+}
@@ -0,0 +1,184 @@
+use std::time::Instant;
+
+use prometheus::TEXT_FORMAT;
+use salvo::http::header::CONTENT_TYPE;
+use salvo::http::{HeaderValue, StatusCode};
+use salvo::{handler, Depot, FlowCtrl, Handler, Request, Response, Writer};
+
+use crate::auth::constant_time_eq;
+use crate::core::errors::{AppError, AppResult};
+use crate::core::salvo::auth::bearer_token;
+use crate::core::salvo::context_inject::{obtain_context, require_context};
+
+/// 未匹配路由的请求使用同一个标签，避免指标的标签过多
+const UNMATCHED_ROUTE: &str = "unmatched";
+/// 匹配到路由时由[`RouteMatched`]设置
+const ROUTE_MATCHED: &str = "route_matched";
+
+/// 按路由及状态码统计请求数量及耗时，根路由需要添加[`RouteMatched`]，否则都视为未匹配的请求
+#[derive(Debug)]
+pub struct HttpMetrics;
+
+/// 添加在根路由上，只有匹配到路由的请求才会经过
+#[derive(Clone, Copy, Debug)]
+pub struct RouteMatched;
+
+#[async_trait::async_trait]
+impl Handler for RouteMatched {
+    async fn handle(&self, _req: &mut Request, depot: &mut Depot, _res: &mut Response, _ctrl: &mut FlowCtrl) {
+        depot.insert(ROUTE_MATCHED, true);
+    }
+}
+
+#[async_trait::async_trait]
+impl Handler for HttpMetrics {
+    async fn handle(&self, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+        let now = Instant::now();
+        ctrl.call_next(req, depot, res).await;
+        let Ok(ctx) = obtain_context(depot) else {
+            return;
+        };
+        let status = res.status_code.unwrap_or(StatusCode::OK);
+        // 匹配到路由的请求也可能返回404，eg:查询的数据不存在
+        let route = if depot.contains_key(ROUTE_MATCHED) { route(req) } else { UNMATCHED_ROUTE.to_string() };
+        ctx.metrics.observe_http(req.method().as_str(), &route, status.as_u16(), now.elapsed());
+    }
+}
+
+/// 指标接口的开关及认证：未开启时返回404，配置了`metrics.token`时需要`Authorization: Bearer {token}`
+#[derive(Clone, Copy, Debug)]
+pub struct MetricsGuard;
+
+#[async_trait::async_trait]
+impl Handler for MetricsGuard {
+    async fn handle(&self, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+        let Some(ctx) = require_context(req, depot, res, ctrl).await else {
+            return;
+        };
+        let config = ctx.reloader.current();
+        if !config.metrics.enabled {
+            res.status_code(StatusCode::NOT_FOUND);
+            ctrl.skip_rest();
+            return;
+        }
+        if let Some(token) = config.metrics.token.as_deref() {
+            if !bearer_token(req).is_some_and(|bearer| constant_time_eq(bearer.as_bytes(), token.as_bytes())) {
+                AppError::Unauthorized("invalid metrics token").write(req, depot, res).await;
+                ctrl.skip_rest();
+            }
+        }
+    }
+}
+
+/// Prometheus指标，需要配合[`MetricsGuard`]使用
+#[handler]
+pub async fn metrics(depot: &mut Depot, res: &mut Response) -> AppResult<()> {
+    let text = obtain_context(depot)?.metrics.render()?;
+    res.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static(TEXT_FORMAT));
+    res.write_body(text).ok();
+    Ok(())
+}
+
+/// 将路径中的参数值替换为参数名：`/user/ins/1` => `/user/ins/{id}`
+fn route(req: &Request) -> String {
+    let mut route = String::new();
+    let mut rest = req.uri().path();
+    for (name, value) in req.params().iter() {
+        if let Some(i) = find_segment(rest, value) {
+            route.push_str(&rest[..i]);
+            route.push_str(&format!("{{{}}}", name.trim_start_matches('*')));
+            rest = &rest[i + value.len()..];
+        }
+    }
+    route.push_str(rest);
+    route
+}
+
+/// 查找以`/`分隔的完整路径段
+fn find_segment(path: &str, value: &str) -> Option<usize> {
+    if value.is_empty() {
+        return None;
+    }
+    path.match_indices(value)
+        .map(|(i, _)| i)
+        .find(|&i| path[..i].ends_with('/') && path[i + value.len()..].chars().next().is_none_or(|c| c == '/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use salvo::test::{ResponseExt, TestClient};
+    use salvo::{handler, Router, Service};
+
+    use common::queue::message::event::ClusterEventProto;
+
+    use super::*;
+    use crate::core::context::Context;
+    use crate::core::salvo::context_inject::ContextInject;
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let config = crate::configs::tests::load("/not/exists/application.yaml", None, &[("APP__DATA_SOURCE__URL", "sqlite::memory:")]);
+        let ctx = Arc::new(Context::new(config).await.unwrap());
+
+        #[handler]
+        async fn hello() -> &'static str {
+            "hello"
+        }
+        #[handler]
+        async fn missing(res: &mut Response) {
+            res.status_code(StatusCode::NOT_FOUND);
+        }
+        let router = Router::new()
+            .hoop(RouteMatched)
+            .push(Router::with_path("user/ins/<id:num>").get(hello))
+            .push(Router::with_path("user/missing/<id:num>").get(missing))
+            .push(Router::with_path("static/<*path>").get(hello))
+            .push(Router::with_path("metrics").hoop(MetricsGuard).get(metrics));
+        let service = Service::new(router).hoop(ContextInject { context: ctx.clone() }).hoop(HttpMetrics);
+
+        for path in ["user/ins/1", "user/ins/2", "user/missing/1", "static/js/app.js", "not/found/1"] {
+            TestClient::get(format!("http://127.0.0.1:5801/{}", path)).send(&service).await;
+        }
+        let text = TestClient::get("http://127.0.0.1:5801/metrics").send(&service).await.take_string().await.unwrap();
+        assert!(text.contains(r#"http_requests_total{method="GET",route="/user/ins/{id}",status="200"} 2"#));
+        assert!(text.contains(r#"http_requests_total{method="GET",route="/static/{path}",status="200"} 1"#));
+        assert!(text.contains(r#"http_requests_total{method="GET",route="unmatched",status="404"} 1"#));
+        assert!(text.contains(r#"http_requests_total{method="GET",route="/user/missing/{id}",status="404"} 1"#));
+        assert!(text.contains(r#"http_request_duration_seconds_count{method="GET",route="/user/ins/{id}",status="200"} 2"#));
+        assert!(text.contains(r#"db_pool_connections{state="idle"}"#));
+        assert!(text.contains("db_pool_max_connections"));
+        assert!(text.contains("cluster_events_sent_total 0"));
+        assert!(text.contains("cluster_events_dropped_total 0"));
+        // 并发采集时计数不变
+        ctx.cluster_event.send(ClusterEventProto::default()).ok();
+        let texts = futures::future::join_all((0..4).map(|_| async {
+            TestClient::get("http://127.0.0.1:5801/metrics").send(&service).await.take_string().await.unwrap()
+        }))
+        .await;
+        assert!(texts.iter().all(|t| t.contains("cluster_events_dropped_total 1")));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_guard() {
+        let service = |f: fn(&mut crate::configs::AppConfig)| async move {
+            let mut config = crate::configs::tests::load("/not/exists/application.yaml", None, &[("APP__DATA_SOURCE__URL", "sqlite::memory:")]);
+            f(&mut config);
+            let ctx = Arc::new(Context::new(config).await.unwrap());
+            let router = Router::with_path("metrics").hoop(MetricsGuard).get(metrics);
+            Service::new(router).hoop(ContextInject { context: ctx })
+        };
+        let status = |res: salvo::Response| res.status_code.unwrap_or(StatusCode::OK);
+
+        let disabled = service(|c| c.metrics.enabled = false).await;
+        assert_eq!(status(TestClient::get("http://127.0.0.1:5801/metrics").send(&disabled).await), StatusCode::NOT_FOUND);
+
+        let protected = service(|c| c.metrics.token = Some("metrics-token-0123456789".to_string())).await;
+        assert_eq!(status(TestClient::get("http://127.0.0.1:5801/metrics").send(&protected).await), StatusCode::UNAUTHORIZED);
+        let res = TestClient::get("http://127.0.0.1:5801/metrics").bearer_auth("wrong-token-0123456789").send(&protected).await;
+        assert_eq!(status(res), StatusCode::UNAUTHORIZED);
+        let res = TestClient::get("http://127.0.0.1:5801/metrics").bearer_auth("metrics-token-0123456789").send(&protected).await;
+        assert_eq!(status(res), StatusCode::OK);
+    }
+}
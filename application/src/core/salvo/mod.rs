@@ -4,28 +4,38 @@ use salvo::oapi::{Components, Object, Ref, RefOr, Schema, schema, ToSchema};
 use crate::core::errors::AppError;
 
 mod error_handler;
+pub mod admin;
 pub mod api_result;
+pub mod auth;
 pub mod context_inject;
+pub mod health;
 pub mod logger;
+pub mod metrics;
+pub mod reloadable;
 
 pub const TRACE_USER_OR_APP_NAME: &str = "USER-APP-IDENT";
 
 /// 外部服务认证标识（HEADER）
 pub const HEADER_APP_TOKEN: &str = "APP-TOKEN";
 
+/// 内部接口的认证方式，接口通过[`auth::permit`]声明需要的权限
+pub const SECURITY_WEB: &str = "Qbee";
+/// 外部接口的认证方式，接口通过[`auth::permit`]声明需要的权限
+pub const SECURITY_APP: &str = "App-Token";
+
 pub const REQUEST_ID_NAME: &str = "x-request-id";
 pub const TRACE_ID_DEFAULT: &str = "unknown";
 pub const DEFAULT_JSON: &str = r#"{"code":"500","message":null,"traceId":null,"data":null}"#;
 
 
-/// 401展示的结构体
-pub fn schema_401(components: &mut Components) -> Schema {
+/// 401、403等展示的结构体
+pub fn schema_status(components: &mut Components, status: StatusCode) -> Schema {
     Schema::from(
         Object::new()
-            .property("code", to_string_schema(StatusCode::UNAUTHORIZED.as_str()))
+            .property("code", to_string_schema(status.as_str()))
             .required("code")
             .required("message")
-            .property("message", to_string_schema(StatusCode::UNAUTHORIZED.canonical_reason().unwrap_or("unknown reason")))
+            .property("message", to_string_schema(status.canonical_reason().unwrap_or("unknown reason")))
             .required("traceId")
             .property("traceId", String::to_schema(components))
             .required("data")
@@ -48,12 +58,12 @@ pub fn schema_500(components: &mut Components) -> Schema {
     )
 }
 
-/// 401结构体
-fn to_401_schema(components: &mut Components) -> RefOr<Schema> {
+/// 401、403等结构体
+fn to_status_schema(components: &mut Components, status: StatusCode) -> RefOr<Schema> {
     let mut symbol = std::any::type_name::<AppError>().replace("::", ".");
     // 需要添加后缀，不然会覆盖正常的展示
-    symbol.push_str("401");
-    let schema = schema_401(components);
+    symbol.push_str(status.as_str());
+    let schema = schema_status(components, status);
     components.schemas.insert(symbol.clone(), schema);
     RefOr::Ref(Ref::new(format!("#/components/schemas/{}", symbol)))
 }
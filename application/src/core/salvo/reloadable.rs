@@ -0,0 +1,111 @@
+use std::sync::{Arc, Once, PoisonError, RwLock};
+
+use salvo::cors::{AllowHeaders, AllowMethods, AllowOrigin, Cors, CorsHandler};
+use salvo::http::StatusCode;
+use salvo::{Depot, FlowCtrl, Handler, Request, Response};
+
+use crate::configs;
+use crate::core::salvo::context_inject::obtain_context;
+
+/// 随配置热加载的cors，`openapi.cors_origin`为空时不处理跨域
+#[derive(Clone, Default)]
+pub struct ReloadableCors {
+    cache: Arc<RwLock<CachedCors>>,
+}
+
+/// 按`cors_origin`缓存的cors处理器，配置未变化时不重新创建
+#[derive(Default)]
+struct CachedCors {
+    origins: Vec<String>,
+    handler: Option<Arc<CorsHandler>>,
+}
+
+impl ReloadableCors {
+    fn handler(&self, origins: &[String]) -> Option<Arc<CorsHandler>> {
+        {
+            let cache = self.cache.read().unwrap_or_else(PoisonError::into_inner);
+            if cache.origins == origins {
+                return cache.handler.clone();
+            }
+        }
+        let handler = create_cors(origins).map(Arc::new);
+        *self.cache.write().unwrap_or_else(PoisonError::into_inner) = CachedCors {
+            origins: origins.to_vec(),
+            handler: handler.clone(),
+        };
+        handler
+    }
+}
+
+#[async_trait::async_trait]
+impl Handler for ReloadableCors {
+    async fn handle(&self, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+        let Ok(ctx) = obtain_context(depot) else {
+            missing_context();
+            return;
+        };
+        let config = ctx.reloader.current();
+        if let Some(cors) = self.handler(&config.openapi.cors_origin) {
+            cors.handle(req, depot, res, ctrl).await;
+        }
+    }
+}
+
+/// 未注入`Context`时不处理跨域、关闭文档，只在第一次时输出日志
+fn missing_context() {
+    static WARN: Once = Once::new();
+    WARN.call_once(|| tracing::warn!("context is not injected, cors and docs are disabled"));
+}
+
+/// 创建cors
+fn create_cors(cors_origin: &[String]) -> Option<CorsHandler> {
+    if cors_origin.is_empty() {
+        return None;
+    }
+    let origin = match cors_origin.contains(&String::from("*")) {
+        true => AllowOrigin::any(),
+        _ => cors_origin.iter().map(String::as_str).collect::<Vec<_>>().into(),
+    };
+
+    let cors = Cors::new()
+        .allow_origin(origin)
+        .allow_methods(AllowMethods::any())
+        .allow_headers(AllowHeaders::any())
+        .into_handler();
+    Some(cors)
+}
+
+/// 开放文档开关，关闭时文档相关的请求返回404
+#[derive(Clone, Copy, Debug)]
+pub enum DocSwitch {
+    /// 内部开放文档
+    Web,
+    /// 外部开放文档
+    Open,
+}
+
+impl DocSwitch {
+    fn enabled(&self, config: &configs::OpenApi) -> bool {
+        match self {
+            DocSwitch::Web => config.enable_web_doc,
+            DocSwitch::Open => config.enable_open_doc,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Handler for DocSwitch {
+    async fn handle(&self, _req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+        let enabled = match obtain_context(depot) {
+            Ok(ctx) => self.enabled(&ctx.reloader.current().openapi),
+            Err(_) => {
+                missing_context();
+                false
+            }
+        };
+        if !enabled {
+            res.status_code(StatusCode::NOT_FOUND);
+            ctrl.skip_rest();
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::Instant;
 
 use futures::FutureExt;
@@ -16,6 +16,9 @@ lazy_static! {
     static ref SHUTDOWN_HOOK: RwLock<Arc<ShutdownHook>> = RwLock::new(Arc::new(ShutdownHook::new()));
 }
 
+/// 收到退出信号后为`true`
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
 type Hook = Pin<Box<dyn Future<Output=()> + Send>>;
 
 pub struct ShutdownHook {
@@ -58,8 +61,9 @@ impl ShutdownHook {
                 }
 
                 let ins = Instant::now();
+                SHUTTING_DOWN.store(true, Ordering::SeqCst);
                 tracing::info!("Terminating process due to signal SIGINT");
-                let count = sync_handlers_cnt.load(Ordering::SeqCst);
+                let count = async_handlers_cnt.load(Ordering::SeqCst);
                 let mut hooks = Vec::new();
                 async_rx.recv_many(&mut hooks, count).await;
                 for h in hooks {
@@ -82,6 +86,10 @@ impl ShutdownHook {
     }
 }
 
+/// 是否已经开始关闭
+pub fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::SeqCst)
+}
 
 /// 等待完成
 pub async fn completed() {
@@ -93,6 +101,7 @@ pub async fn completed() {
 }
 
 /// 添加处理器
+#[allow(dead_code)]
 pub async fn push_sync<F>(future: F)
 where
     F: Future<Output=()> + Send + 'static,
@@ -103,7 +112,7 @@ where
     }
 }
 
-/// 添加处理器
+/// 添加处理器，收到退出信号后按添加的顺序依次执行
 pub async fn push<F>(future: F)
 where
     F: Future<Output=()> + Send + 'static,
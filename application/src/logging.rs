@@ -1,21 +1,36 @@
-use crate::core::errors::AppResult;
+use crate::configs::ConfigErrors;
+use crate::core::errors::{AppError, AppResult};
+use crate::telemetry::{self, Otlp};
 use nu_ansi_term::Style;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_sdk::trace::SdkTracerProvider;
 use sea_orm::sqlx::types::chrono;
-use serde::Deserialize;
-use smallvec::SmallVec;
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fmt::Write;
 use std::path::Path;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
 use std::thread::ThreadId;
+use tracing::field::{Field, Visit};
 use tracing::level_filters::LevelFilter;
-use tracing::{Event, Level, Metadata, Subscriber};
+use tracing::{Event, Level, Subscriber};
+use file_rotate::compression::Compression;
+use file_rotate::suffix::AppendCount;
+use file_rotate::{ContentLimit, FileRotate, TimeFrequency};
 use tracing_appender::non_blocking::WorkerGuard;
-use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::format::{JsonFields, Writer};
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriterExt};
 use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::filter::Directive;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::registry::{LookupSpan, Scope};
 use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
 
 /// 日志配置文件
 #[derive(Clone, Debug, Deserialize, PartialEq)]
@@ -23,7 +38,8 @@ pub struct Logging {
     /// 日志级别
     #[serde(default = "default_log_level")]
     pub level: String,
-    /// 多模块日志等级配置，eg:my_crate::module=trace
+    /// 多模块日志等级配置，`EnvFilter`格式，eg:`my_crate::module=trace`、`[request{user=admin}]=debug`，
+    /// 按最长前缀匹配，`RUST_LOG`环境变量中的指令优先
     #[serde(default)]
     pub level_list: Option<Vec<String>>,
     /// 是否输出到文件
@@ -32,69 +48,434 @@ pub struct Logging {
     /// 日志文件路径
     #[serde(default)]
     pub path: Option<String>,
+    /// 输出到文件时是否同时输出到控制台
+    #[serde(default = "default_enable_console")]
+    pub enable_console: bool,
+    /// 日志文件滚动策略
+    #[serde(default)]
+    pub rotation: Rotation,
+    /// 日志输出格式：`text`/`json`，默认`text`
+    #[serde(default)]
+    pub format: LogFormat,
+    /// 以OTLP导出span
+    #[serde(default)]
+    pub otlp: Otlp,
+}
+
+/// 日志输出格式
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// 便于阅读的单行文本
+    #[default]
+    Text,
+    /// 每条日志一个JSON对象，便于日志采集
+    Json,
+}
+
+/// 日志文件滚动策略
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Rotation {
+    /// 滚动周期：`hourly`/`daily`/`size`/`never`，默认`daily`
+    #[serde(default)]
+    pub policy: RotationPolicy,
+    /// `size`策略下单个文件的最大大小（MB）
+    #[serde(default = "default_max_size_mb")]
+    pub max_size_mb: u64,
+    /// 保留的历史文件数量，默认不限制
+    #[serde(default)]
+    pub max_files: Option<usize>,
+    /// 是否使用gzip压缩历史文件
+    #[serde(default)]
+    pub compress: bool,
+}
+
+impl Default for Rotation {
+    fn default() -> Self {
+        Self {
+            policy: RotationPolicy::default(),
+            max_size_mb: default_max_size_mb(),
+            max_files: None,
+            compress: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RotationPolicy {
+    Hourly,
+    #[default]
+    Daily,
+    /// 按文件大小滚动
+    Size,
+    /// 不滚动
+    Never,
 }
 
 pub fn default_log_level() -> String {
     "info".to_owned()
 }
 
-pub fn setup_logging(config: &Logging) -> AppResult<WorkerGuard> {
-    let file_non_blocking = config.enable_file.then_some(config.path.as_ref()).flatten().and_then(|path| {
-        let file_path = Path::new(path);
-        file_path.parent().map(|folder_path| {
-            let file_name: &Path = match (file_path.file_name(), file_path.extension()) {
-                (Some(name), Some(_)) => name.as_ref(),
-                _ => "application".as_ref(),
-            };
-            let file_appender = tracing_appender::rolling::daily(folder_path, file_name);
-            tracing_appender::non_blocking(file_appender)
-        })
+fn default_enable_console() -> bool {
+    true
+}
+
+fn default_max_size_mb() -> u64 {
+    100
+}
+
+impl Logging {
+    pub(crate) fn validate(&self, errors: &mut ConfigErrors) {
+        if let Err(e) = LevelFilter::from_str(&self.level.to_lowercase()) {
+            errors.push("logging.level", format!("`{}`: {}", self.level, e));
+        }
+        for (i, x) in self.level_list.iter().flatten().enumerate() {
+            if let Err(e) = parse_directive(x) {
+                errors.push(format!("logging.level_list[{}]", i), e);
+            }
+        }
+        if self.enable_file && self.path.as_deref().is_none_or(|p| p.trim().is_empty()) {
+            errors.push("logging.path", "must be set when `enable_file` is true");
+        }
+        if self.rotation.policy == RotationPolicy::Size && self.rotation.max_size_mb == 0 {
+            errors.push("logging.rotation.max_size_mb", "must be greater than 0");
+        }
+        if self.rotation.max_files == Some(0) {
+            errors.push("logging.rotation.max_files", "must be greater than 0");
+        }
+        self.otlp.validate(errors);
+    }
+}
+
+/// 解析`EnvFilter`格式的指令`target[span{field=value}]=level`，返回等级之前的选择器及等级：
+/// 只有等级时选择器为空，省略等级时为`trace`
+fn parse_directive(directive: &str) -> Result<(Option<String>, LevelFilter), String> {
+    let directive = directive.trim();
+    Directive::from_str(directive).map_err(|e| format!("`{}`: {}", directive, e))?;
+    if let Ok(level) = LevelFilter::from_str(directive) {
+        return Ok((None, level));
+    }
+    // 字段过滤中也可能有`=`，只有最后一个`=`之后是等级时才拆分
+    match directive.rsplit_once('=').map(|(selector, level)| (selector, LevelFilter::from_str(level.trim()))) {
+        Some((selector, Ok(level))) => Ok((Some(selector.trim().to_string()), level)),
+        _ => Ok((Some(directive.to_string()), LevelFilter::TRACE)),
+    }
+}
+
+/// 程序运行期间需要保持的日志资源，释放时输出剩余的日志并导出剩余的span
+pub struct LoggingGuard {
+    _workers: Vec<WorkerGuard>,
+    tracer_provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for LoggingGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.tracer_provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("shutdown tracer provider fail: {}", e);
+            }
+        }
+    }
+}
+
+pub fn setup_logging(config: &Logging) -> AppResult<(LoggingGuard, LoggingHandle)> {
+    let mut guards = Vec::new();
+    let file = config.enable_file.then_some(config.path.as_ref()).flatten().map(|path| {
+        let (non_blocking, guard) = tracing_appender::non_blocking(file_appender(Path::new(path), &config.rotation));
+        guards.push(guard);
+        non_blocking
     });
-    let (non_blocking, guard) = file_non_blocking.unwrap_or_else(|| tracing_appender::non_blocking(std::io::stdout()));
+    // 未输出到文件时总是输出到控制台
+    let console = (config.enable_console || file.is_none()).then(|| {
+        let (non_blocking, guard) = tracing_appender::non_blocking(std::io::stdout());
+        guards.push(guard);
+        non_blocking
+    });
+    let writer = match (console, file) {
+        (Some(console), Some(file)) => BoxMakeWriter::new(console.and(file)),
+        (Some(console), None) => BoxMakeWriter::new(console),
+        (None, Some(file)) => BoxMakeWriter::new(file),
+        (None, None) => unreachable!("console is enabled when file is disabled"),
+    };
+
+    // `RUST_LOG`中的指令覆盖配置的日志等级
+    let rust_log = std::env::var(EnvFilter::DEFAULT_ENV).ok().filter(|v| !v.trim().is_empty());
+    let (levels, rust_log, invalid) = LogLevels::with_env(config, rust_log)?;
+    let (filter, reload_handle) = reload::Layer::new(levels.filter());
+    let handle = LoggingHandle::new(reload_handle, levels, rust_log);
+
+    let tracer_provider = config.otlp.enabled.then(|| telemetry::tracer_provider(&config.otlp)).transpose()?;
+    let otel = tracer_provider.as_ref().map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME"))));
+
+    let format = TracingFormat::new(config.format);
+    let fmt = tracing_subscriber::fmt::layer().with_ansi(false).with_writer(writer);
+    let registry = tracing_subscriber::registry().with(filter).with(otel);
+    match config.format {
+        LogFormat::Text => registry.with(fmt.event_format(format)).init(),
+        // span的字段以JSON记录，输出时保留字段类型
+        LogFormat::Json => registry.with(fmt.fmt_fields(JsonFields::new()).event_format(format)).init(),
+    }
+    if let Some(e) = invalid {
+        tracing::warn!("ignore invalid {}, use the configured levels: {}", EnvFilter::DEFAULT_ENV, e);
+    }
+
+    // 这些 guard 的作用是确保 NonBlocking 在程序运行期间保持活动状态
+    let guard = LoggingGuard {
+        _workers: guards,
+        tracer_provider,
+    };
+    Ok((guard, handle))
+}
+
+/// 按滚动策略创建日志文件，历史文件以`.1`、`.2`...结尾，数字越小越新
+fn file_appender(path: &Path, rotation: &Rotation) -> FileRotate<AppendCount> {
+    let file_name: &Path = match (path.file_name(), path.extension()) {
+        (Some(name), Some(_)) => name.as_ref(),
+        _ => "application".as_ref(),
+    };
+    let file_path = path.parent().unwrap_or(Path::new(".")).join(file_name);
+    let limit = match rotation.policy {
+        RotationPolicy::Hourly => ContentLimit::Time(TimeFrequency::Hourly),
+        RotationPolicy::Daily => ContentLimit::Time(TimeFrequency::Daily),
+        RotationPolicy::Size => ContentLimit::BytesSurpassed(rotation.max_size_mb as usize * 1024 * 1024),
+        RotationPolicy::Never => ContentLimit::None,
+    };
+    let compression = if rotation.compress { Compression::OnRotate(0) } else { Compression::None };
+    FileRotate::new(file_path, AppendCount::new(rotation.max_files.unwrap_or(usize::MAX)), limit, compression, None)
+}
+
+/// 运行期间修改日志等级，配置的等级可被临时覆盖
+#[derive(Clone)]
+pub struct LoggingHandle {
+    filter: reload::Handle<EnvFilter, Registry>,
+    state: Arc<Mutex<LevelState>>,
+}
+
+/// 配置的日志等级及运行期间覆盖的等级
+struct LevelState {
+    config: LogLevels,
+    /// `RUST_LOG`环境变量，重新加载配置时仍然覆盖配置的日志等级
+    env: Option<String>,
+    overrides: BTreeMap<Option<String>, LevelOverride>,
+    next_id: u64,
+}
+
+struct LevelOverride {
+    id: u64,
+    level: LevelFilter,
+    expires_at: Option<chrono::DateTime<chrono::Local>>,
+}
+
+/// 当前生效的日志等级
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EffectiveLevels {
+    /// 默认日志等级
+    pub level: String,
+    /// 各模块的日志等级
+    pub targets: Vec<TargetLevel>,
+    /// 运行期间覆盖的日志等级
+    pub overrides: Vec<OverrideLevel>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TargetLevel {
+    pub target: String,
+    pub level: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OverrideLevel {
+    /// 模块，为空时覆盖默认日志等级
+    pub target: Option<String>,
+    pub level: String,
+    /// 到期后恢复为配置的等级，为空时一直生效
+    pub expires_at: Option<String>,
+}
+
+impl LoggingHandle {
+    fn new(filter: reload::Handle<EnvFilter, Registry>, config: LogLevels, env: Option<String>) -> Self {
+        let state = LevelState {
+            config,
+            env,
+            overrides: BTreeMap::new(),
+            next_id: 0,
+        };
+        Self {
+            filter,
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// 按配置重新设置日志等级及多模块日志等级，运行期间覆盖的等级继续生效
+    pub fn update(&self, config: &Logging) -> AppResult<()> {
+        let mut state = self.lock();
+        state.config = LogLevels::new(config, state.env.as_deref())?;
+        self.apply(&state)
+    }
+
+    /// 当前生效的日志等级
+    pub fn levels(&self) -> EffectiveLevels {
+        let state = self.lock();
+        let levels = state.effective();
+        EffectiveLevels {
+            level: levels.default_level.to_string(),
+            targets: levels
+                .directives
+                .iter()
+                .map(|(target, level)| TargetLevel {
+                    target: target.clone(),
+                    level: level.to_string(),
+                })
+                .collect(),
+            overrides: state
+                .overrides
+                .iter()
+                .map(|(target, o)| OverrideLevel {
+                    target: target.clone(),
+                    level: o.level.to_string(),
+                    expires_at: o.expires_at.map(|t| t.format("%Y-%m-%dT%H:%M:%S%:z").to_string()),
+                })
+                .collect(),
+        }
+    }
+
+    /// 覆盖模块（`target`为空时为默认）的日志等级，设置了`ttl`时到期后恢复，
+    /// `target`支持span及字段过滤，eg:`[request{user=admin}]`
+    pub fn set(&self, target: Option<String>, level: LevelFilter, ttl: Option<Duration>) -> AppResult<()> {
+        let target = match target.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+            Some(t) => parse_directive(&format!("{}={}", t, level)).map_err(AppError::BadRequest)?.0,
+            None => None,
+        };
+        let mut state = self.lock();
+        state.next_id += 1;
+        let id = state.next_id;
+        let expires_at = ttl.map(|ttl| chrono::Local::now() + ttl);
+        state.overrides.insert(target.clone(), LevelOverride { id, level, expires_at });
+        self.apply(&state)?;
+        drop(state);
+        tracing::info!("log level of `{}` is set to {}, ttl: {:?}", target.as_deref().unwrap_or("*"), level, ttl);
+
+        if let Some(ttl) = ttl {
+            let handle = self.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(ttl).await;
+                handle.revert(target, Some(id));
+            });
+        }
+        Ok(())
+    }
+
+    /// 恢复为配置的日志等级，`target`为空时恢复所有覆盖的等级
+    pub fn reset(&self, target: Option<&str>) -> AppResult<()> {
+        let mut state = self.lock();
+        match target {
+            Some(target) => {
+                let target = Some(target.trim().to_string()).filter(|t| !t.is_empty());
+                state.overrides.remove(&target);
+            }
+            None => state.overrides.clear(),
+        }
+        self.apply(&state)
+    }
 
-    let mut format = TracingFormat::new(LevelFilter::from_str(&config.level.to_lowercase())?);
-    if let Some(list) = &config.level_list {
-        for x in list {
-            let t: Vec<&str> = x.split('=').collect();
-            format.add_target(t[0], LevelFilter::from_str(&t[1].to_lowercase())?);
+    /// 到期恢复，覆盖已被更新时不处理
+    fn revert(&self, target: Option<String>, id: Option<u64>) {
+        let mut state = self.lock();
+        if id.is_some() && state.overrides.get(&target).map(|o| o.id) != id {
+            return;
+        }
+        state.overrides.remove(&target);
+        match self.apply(&state) {
+            Ok(_) => tracing::info!("log level of `{}` is reverted.", target.as_deref().unwrap_or("*")),
+            Err(e) => tracing::warn!("revert log level fail: {}", e),
         }
     }
 
-    let fmt = tracing_subscriber::fmt::layer()
-        .with_ansi(false)
-        .with_writer(non_blocking)
-        .event_format(format);
-    tracing_subscriber::registry().with(fmt).init();
+    fn apply(&self, state: &LevelState) -> AppResult<()> {
+        self.filter.reload(state.effective().filter()).map_err(|e| AppError::Logging(e.to_string()))
+    }
 
-    // 这个 guard 的作用是确保 NonBlocking 在程序运行期间保持活动状态
-    Ok(guard)
+    fn lock(&self) -> MutexGuard<'_, LevelState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
 }
 
-pub(crate) struct TracingFormat {
-    directives: SmallVec<[StaticDirective; 8]>,
+impl LevelState {
+    fn effective(&self) -> LogLevels {
+        let mut levels = self.config.clone();
+        for (target, o) in &self.overrides {
+            levels.set(target.clone(), o.level);
+        }
+        levels
+    }
+}
+
+/// 默认日志等级及`EnvFilter`格式的指令，选择器相同时后设置的覆盖之前的
+#[derive(Clone, Debug, PartialEq)]
+struct LogLevels {
     default_level: LevelFilter,
+    directives: Vec<(String, LevelFilter)>,
 }
 
-impl TracingFormat {
-    pub fn new(default_level: LevelFilter) -> Self {
-        Self {
-            directives: SmallVec::new(),
-            default_level,
+impl LogLevels {
+    /// 配置的日志等级，`env`中的指令优先
+    fn new(config: &Logging, env: Option<&str>) -> AppResult<Self> {
+        let mut levels = Self {
+            default_level: LevelFilter::from_str(&config.level.to_lowercase())?,
+            directives: Vec::new(),
+        };
+        let env_directives = env.into_iter().flat_map(|env| env.split(','));
+        for x in config.level_list.iter().flatten().map(String::as_str).chain(env_directives) {
+            if x.trim().is_empty() {
+                continue;
+            }
+            let (selector, level) = parse_directive(x).map_err(AppError::Logging)?;
+            levels.set(selector, level);
         }
+        Ok(levels)
     }
 
-    pub fn add_target(&mut self, target: impl Into<String>, level: impl Into<LevelFilter>) {
-        self.directives.push(StaticDirective::new(target.into(), level.into()));
+    /// `env`无效时忽略，使用配置的日志等级，并返回`env`的错误
+    fn with_env(config: &Logging, env: Option<String>) -> AppResult<(Self, Option<String>, Option<AppError>)> {
+        match Self::new(config, env.as_deref()) {
+            Ok(levels) => Ok((levels, env, None)),
+            Err(e) if env.is_some() => Ok((Self::new(config, None)?, None, Some(e))),
+            Err(e) => Err(e),
+        }
     }
 
-    pub fn disable(&self, meta: &Metadata) -> bool {
-        let level = meta.level();
-        let target = meta.target();
-        match self.directives.iter().find(|d| d.target.starts_with(target)) {
-            None => self.default_level < *level,
-            Some(d) => d.level < *level,
+    fn set(&mut self, selector: Option<String>, level: LevelFilter) {
+        match selector {
+            None => self.default_level = level,
+            Some(selector) => {
+                self.directives.retain(|(s, _)| *s != selector);
+                self.directives.push((selector, level));
+            }
         }
     }
+
+    /// 最长前缀匹配，span及字段过滤由`EnvFilter`实现，被过滤的日志不会创建
+    fn filter(&self) -> EnvFilter {
+        let filter = EnvFilter::default().add_directive(self.default_level.into());
+        self.directives.iter().fold(filter, |filter, (selector, level)| {
+            // 指令在设置时已经校验过
+            match format!("{}={}", selector, level).parse() {
+                Ok(directive) => filter.add_directive(directive),
+                Err(_) => filter,
+            }
+        })
+    }
+}
+
+pub(crate) struct TracingFormat {
+    format: LogFormat,
+}
+
+impl TracingFormat {
+    fn new(format: LogFormat) -> Self {
+        Self { format }
+    }
 }
 
 impl<S, N> FormatEvent<S, N> for TracingFormat
@@ -105,8 +486,8 @@ where
     fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
         let meta = event.metadata();
 
-        if self.disable(meta) {
-            return Ok(());
+        if self.format == LogFormat::Json {
+            return format_json(ctx, writer, event);
         }
 
         let t = chrono::Local::now();
@@ -137,6 +518,94 @@ where
     }
 }
 
+/// 输出JSON格式的日志：时间、等级、target、线程、span链及事件字段
+fn format_json<S, N>(ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    let meta = event.metadata();
+    let mut object = Map::new();
+    object.insert("timestamp".into(), chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string().into());
+    object.insert("level".into(), meta.level().as_str().into());
+    object.insert("target".into(), meta.target().into());
+
+    let current_thread = std::thread::current();
+    object.insert("thread".into(), current_thread.name().unwrap_or_default().into());
+    object.insert("thread_id".into(), thread_id(current_thread.id()).into());
+
+    let mut trace = None;
+    let mut spans = Vec::new();
+    for span in ctx.event_scope().into_iter().flat_map(Scope::from_root) {
+        let mut fields = Map::new();
+        fields.insert("name".into(), span.metadata().name().into());
+        let exts = span.extensions();
+        if let Some(formatted) = exts.get::<FormattedFields<N>>() {
+            match serde_json::from_str::<Map<String, Value>>(&formatted.fields) {
+                Ok(values) => fields.extend(values),
+                Err(_) if !formatted.fields.is_empty() => {
+                    fields.insert("fields".into(), formatted.fields.as_str().into());
+                }
+                Err(_) => {}
+            }
+        }
+        if let Some(t) = fields.get("trace") {
+            trace = Some(t.clone());
+        }
+        spans.push(Value::Object(fields));
+    }
+    if let Some(trace) = trace {
+        object.insert("trace".into(), trace);
+    }
+    object.insert("spans".into(), spans.into());
+
+    let mut fields = Map::new();
+    event.record(&mut JsonVisitor(&mut fields));
+    object.insert("fields".into(), fields.into());
+
+    let json = serde_json::to_string(&object).map_err(|_| fmt::Error)?;
+    writeln!(writer, "{}", json)
+}
+
+/// 按类型记录事件字段
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.0.insert(field.name().into(), value.to_string().into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().into(), format!("{:?}", value).into());
+    }
+}
+
+/// `ThreadId(3)` => `3`
+fn thread_id(id: ThreadId) -> u64 {
+    let id = format!("{:?}", id);
+    id.trim_start_matches("ThreadId(").trim_end_matches(')').parse().unwrap_or_default()
+}
+
 struct FmtThreadName<'a> {
     name: &'a str,
     id: ThreadId,
@@ -231,14 +700,209 @@ where
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-struct StaticDirective {
-    target: String,
-    level: LevelFilter,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::Mutex;
+
+    /// 收集输出的日志
+    #[derive(Clone, Default)]
+    struct CaptureWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for CaptureWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_format() {
+        let config = Logging { format: LogFormat::Json, ..logging("info", &["noisy=warn"]) };
+        let writer = CaptureWriter::default();
+        let make_writer = writer.clone();
+        let fmt = tracing_subscriber::fmt::layer()
+            .with_writer(move || make_writer.clone())
+            .fmt_fields(JsonFields::new())
+            .event_format(TracingFormat::new(config.format));
+        let subscriber = tracing_subscriber::registry().with(LogLevels::new(&config, None).unwrap().filter()).with(fmt);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("tfp-web", trace = %"9f3c", method = "GET");
+            span.in_scope(|| {
+                tracing::info!(count = 3, ratio = 0.5, ok = true, user = ?Some("admin"), "hello {}", "world");
+                tracing::info!(target: "noisy", "filtered");
+            });
+        });
+
+        let output = String::from_utf8(writer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let log: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(log["level"], "INFO");
+        assert_eq!(log["target"], module_path!());
+        assert_eq!(log["trace"], "9f3c");
+        assert_eq!(log["spans"][0]["name"], "tfp-web");
+        assert_eq!(log["spans"][0]["method"], "GET");
+        assert_eq!(log["fields"]["message"], "hello world");
+        assert_eq!(log["fields"]["count"], 3);
+        assert_eq!(log["fields"]["ratio"], 0.5);
+        assert_eq!(log["fields"]["ok"], true);
+        assert_eq!(log["fields"]["user"], "Some(\"admin\")");
+        assert!(log["timestamp"].is_string());
+        assert!(log["thread"].is_string());
+    }
+
+    #[test]
+    fn test_file_appender() {
+        use std::io::Write;
+        let dir = std::env::temp_dir().join(format!("app-logs-{}", std::process::id()));
+        let rotation = Rotation {
+            policy: RotationPolicy::Size,
+            max_size_mb: 1,
+            max_files: Some(2),
+            compress: true,
+        };
+        let mut appender = file_appender(&dir.join("app.log"), &rotation);
+        let line = vec![b'x'; 1024 * 1024 + 1];
+        for _ in 0..4 {
+            appender.write_all(&line).unwrap();
+            appender.flush().unwrap();
+        }
+
+        let mut files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name().into_string().unwrap()).collect();
+        files.sort();
+        assert_eq!(files, vec!["app.log", "app.log.1.gz", "app.log.2.gz"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_override_levels() {
+        let config = logging("info", &["sqlx::query=warn"]);
+        let levels = LogLevels::new(&config, None).unwrap();
+        let (filter, reload_handle) = reload::Layer::new(levels.filter());
+        let handle = LoggingHandle::new(reload_handle, levels, None);
+        let writer = CaptureWriter::default();
+        let make_writer = writer.clone();
+        let fmt = tracing_subscriber::fmt::layer().with_writer(move || make_writer.clone()).event_format(TracingFormat::new(config.format));
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(filter).with(fmt));
+
+        tracing::debug!(target: "sqlx::query", "hidden");
+        handle.set(Some("sqlx::query".into()), LevelFilter::DEBUG, Some(Duration::from_secs(3600))).unwrap();
+        tracing::debug!(target: "sqlx::query", "shown");
+        let levels = handle.levels();
+        assert_eq!(levels.level, "info");
+        assert_eq!(levels.targets[0].level, "debug");
+        assert!(levels.overrides[0].expires_at.is_some());
+
+        // 直接执行到期恢复，已被更新的覆盖不处理
+        let target = Some("sqlx::query".to_string());
+        let id = handle.lock().overrides[&target].id;
+        handle.revert(target.clone(), Some(id + 1));
+        assert_eq!(handle.levels().targets[0].level, "debug");
+        handle.revert(target, Some(id));
+        tracing::debug!(target: "sqlx::query", "reverted");
+        let levels = handle.levels();
+        assert_eq!(levels.targets[0].level, "warn");
+        assert!(levels.overrides.is_empty());
+
+        let invalid = handle.set(Some("sqlx[{".into()), LevelFilter::DEBUG, None);
+        assert!(invalid.is_err_and(|e| e.status_code() == salvo::http::StatusCode::BAD_REQUEST));
+        handle.set(None, LevelFilter::WARN, None).unwrap();
+        tracing::info!("hidden");
+        handle.reset(None).unwrap();
+        tracing::info!("restored");
+
+        let output = String::from_utf8(writer.0.lock().unwrap().clone()).unwrap();
+        // 忽略修改日志等级时输出的日志
+        let messages: Vec<&str> = output.lines().filter(|l| !l.contains("log level")).filter_map(|l| l.rsplit(' ').next()).collect();
+        assert_eq!(messages, vec!["shown", "restored"]);
+    }
+
+    fn logging(level: &str, level_list: &[&str]) -> Logging {
+        Logging {
+            level: level.into(),
+            level_list: Some(level_list.iter().map(|x| x.to_string()).collect()),
+            enable_file: false,
+            path: None,
+            enable_console: true,
+            rotation: Rotation::default(),
+            format: LogFormat::Text,
+            otlp: Otlp::default(),
+        }
+    }
+
+    /// 按过滤规则输出日志，返回输出的消息
+    fn capture(levels: &LogLevels, f: impl FnOnce()) -> Vec<String> {
+        let writer = CaptureWriter::default();
+        let make_writer = writer.clone();
+        let fmt = tracing_subscriber::fmt::layer().with_writer(move || make_writer.clone()).event_format(TracingFormat::new(LogFormat::Text));
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(levels.filter()).with(fmt), f);
+        let output = String::from_utf8(writer.0.lock().unwrap().clone()).unwrap();
+        output.lines().filter_map(|l| l.rsplit(' ').next()).map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_directive() {
+        let target = |s: &str| Some(s.to_string());
+        assert_eq!(parse_directive("info").unwrap(), (None, LevelFilter::INFO));
+        assert_eq!(parse_directive("WARN").unwrap(), (None, LevelFilter::WARN));
+        assert_eq!(parse_directive(" sea_orm=debug ").unwrap(), (target("sea_orm"), LevelFilter::DEBUG));
+        assert_eq!(parse_directive("sqlx").unwrap(), (target("sqlx"), LevelFilter::TRACE));
+        assert_eq!(parse_directive("my_crate::module=off").unwrap(), (target("my_crate::module"), LevelFilter::OFF));
+        assert_eq!(parse_directive("[request]=debug").unwrap(), (target("[request]"), LevelFilter::DEBUG));
+        assert_eq!(parse_directive("app[request{user=admin}]=trace").unwrap(), (target("app[request{user=admin}]"), LevelFilter::TRACE));
+        assert_eq!(parse_directive("[{user=admin}]").unwrap(), (target("[{user=admin}]"), LevelFilter::TRACE));
+        assert!(parse_directive("sqlx=loud").is_err());
+        assert!(parse_directive("sqlx[{").is_err());
+        assert!(parse_directive("[request{user=admin]=info").is_err());
+    }
+
+    #[test]
+    fn test_longest_prefix() {
+        let levels = LogLevels::new(&logging("info", &["sea_orm=warn", "sea_orm::query=debug"]), None).unwrap();
+        let messages = capture(&levels, || {
+            tracing::info!(target: "sea", "sea-info");
+            tracing::debug!(target: "sea", "sea-debug");
+            tracing::info!(target: "sea_orm", "orm-info");
+            tracing::warn!(target: "sea_orm::driver", "driver-warn");
+            tracing::info!(target: "sea_orm::driver", "driver-info");
+            tracing::debug!(target: "sea_orm::query", "query-debug");
+            tracing::trace!(target: "sea_orm::query", "query-trace");
+        });
+        assert_eq!(messages, vec!["sea-info", "driver-warn", "query-debug"]);
+    }
+
+    #[test]
+    fn test_span_and_field_filter() {
+        let levels = LogLevels::new(&logging("info", &["[request]=debug", "[job{user=admin}]=trace"]), None).unwrap();
+        let messages = capture(&levels, || {
+            tracing::debug!("outside");
+            tracing::info_span!("request").in_scope(|| tracing::debug!("in-request"));
+            tracing::info_span!("job", user = "guest").in_scope(|| tracing::trace!("guest-job"));
+            tracing::info_span!("job", user = "admin").in_scope(|| tracing::trace!("admin-job"));
+        });
+        assert_eq!(messages, vec!["in-request", "admin-job"]);
+    }
 
-impl StaticDirective {
-    pub fn new(target: String, level: LevelFilter) -> Self {
-        Self { target, level }
+    #[test]
+    fn test_rust_log_override() {
+        let config = logging("info", &["sea_orm=debug", "sqlx=warn"]);
+        let levels = LogLevels::new(&config, Some("warn,sea_orm=error,hyper=trace")).unwrap();
+        assert_eq!(levels.default_level, LevelFilter::WARN);
+        assert_eq!(
+            levels.directives,
+            vec![("sqlx".to_string(), LevelFilter::WARN), ("sea_orm".to_string(), LevelFilter::ERROR), ("hyper".to_string(), LevelFilter::TRACE)]
+        );
+        assert!(LogLevels::new(&config, Some("sqlx=loud")).is_err());
+        // 无效的`RUST_LOG`不影响启动
+        let (levels, env, invalid) = LogLevels::with_env(&config, Some("sqlx=loud".to_string())).unwrap();
+        assert_eq!((levels.default_level, env, invalid.is_some()), (LevelFilter::INFO, None, true));
+        assert!(LogLevels::with_env(&logging("loud", &[]), Some("warn".to_string())).is_err());
     }
 }
@@ -0,0 +1,295 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use salvo::oapi::ToSchema;
+use sea_orm::sqlx::types::chrono::Utc;
+use sea_orm::{Set, TransactionTrait};
+use serde::Deserialize;
+
+use common::domain::user::user::{ActiveModel, Model as User};
+use common::domain::user::user_token::{self, PURPOSE_REFRESH, PURPOSE_RESET_PASSWORD, PURPOSE_VERIFY_EMAIL};
+use common::domain::user::{UserRepository, UserTokenRepository};
+use common::email::Template;
+
+use crate::auth::{digest, password, random_token};
+use crate::core::context::Context;
+use crate::core::errors::{AppError, AppResult};
+use crate::service::email_outbox_service::EmailOutboxService;
+use crate::service::user_service::{SaveUser, UserService, UserVo};
+
+/// 验证邮箱的链接，相对于服务的根路径
+const VERIFY_EMAIL_PATH: &str = "email/verify";
+/// 重置密码的链接，相对于服务的根路径
+const RESET_PASSWORD_PATH: &str = "password/reset";
+/// 令牌不存在、已使用或已过期
+const INVALID_TOKEN: &str = "invalid or expired token";
+
+/// 验证邮箱的邮件，变量：`username`、`hours`、`link`
+fn verify_email_template() -> Template {
+    Template::new("验证邮箱", include_str!("../../templates/email/verify_email.txt")).html(include_str!("../../templates/email/verify_email.html"))
+}
+
+/// 重置密码的邮件，变量：`username`、`minutes`、`link`
+fn reset_password_template() -> Template {
+    Template::new("重置密码", include_str!("../../templates/email/reset_password.txt")).html(include_str!("../../templates/email/reset_password.html"))
+}
+
+pub struct AccountService;
+
+impl AccountService {
+    /// 注册并发送验证邮件，发送失败时可以重新发送
+    #[tracing::instrument(skip_all, fields(username = %req.username))]
+    pub(crate) async fn signup(ctx: &Arc<Context>, req: Signup) -> AppResult<UserVo> {
+        if !ctx.reloader.current().account.enable_signup {
+            return Err(AppError::Forbidden("signup is disabled".to_string()));
+        }
+        let save = SaveUser { username: req.username, email: req.email, phone: req.phone };
+        let user = UserService::create_with_password(ctx, save, Some(req.password)).await?;
+        if let Err(e) = Self::send_verification(ctx, user.id, &user.username, &user.email).await {
+            tracing::warn!("queue verification email to user {} fail: {}", user.id, e);
+        }
+        Ok(user)
+    }
+
+    /// 使用邮件中的令牌验证邮箱
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn verify_email(ctx: &Arc<Context>, token: &str) -> AppResult<()> {
+        let (token, user) = Self::find_token(ctx, PURPOSE_VERIFY_EMAIL, token).await?;
+        let txn = ctx.db.begin().await?;
+        if !UserTokenRepository::consume(&txn, token.id, Utc::now()).await? {
+            return Err(AppError::BadRequest(INVALID_TOKEN.to_string()));
+        }
+        if user.email_verified_at.is_none() {
+            let mut model: ActiveModel = user.into();
+            model.email_verified_at = Set(Some(Utc::now()));
+            UserRepository::update(&txn, model).await?;
+        }
+        txn.commit().await?;
+        tracing::info!("email of user {} verified", token.user_id);
+        Ok(())
+    }
+
+    /// 重新发送验证邮件，不提示邮箱是否存在
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn resend_verification(ctx: &Arc<Context>, req: EmailRequest) -> AppResult<()> {
+        let Some(user) = UserRepository::find_by_email(&ctx.db, req.email.trim()).await? else {
+            return Ok(());
+        };
+        if user.email_verified_at.is_none() {
+            if let Err(e) = Self::send_verification(ctx, user.id, &user.username, &user.email).await {
+                tracing::warn!("queue verification email to user {} fail: {}", user.id, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// 发送重置密码邮件，不提示邮箱是否存在
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn forgot_password(ctx: &Arc<Context>, req: EmailRequest) -> AppResult<()> {
+        let Some(user) = UserRepository::find_by_email(&ctx.db, req.email.trim()).await? else {
+            return Ok(());
+        };
+        let ttl = ctx.reloader.current().account.reset_password_ttl;
+        let token = Self::issue(ctx, user.id, PURPOSE_RESET_PASSWORD, ttl).await?;
+        let minutes = (ttl.as_secs() / 60).to_string();
+        let link = link(ctx, RESET_PASSWORD_PATH, &token);
+        let vars = [("username", user.username.as_str()), ("minutes", &minutes), ("link", &link)];
+        EmailOutboxService::enqueue(ctx, &user.email, &reset_password_template(), &vars, true).await?;
+        Ok(())
+    }
+
+    /// 使用邮件中的令牌重置密码，同时解除锁定、视为已验证邮箱，之前签发的刷新令牌失效
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn reset_password(ctx: &Arc<Context>, req: ResetPassword) -> AppResult<()> {
+        let (token, user) = Self::find_token(ctx, PURPOSE_RESET_PASSWORD, &req.token).await?;
+        let config = ctx.reloader.current().password.clone();
+        // 密码不满足策略时令牌仍然可用
+        config.check(&req.new_password, &user.username)?;
+        let hashed = password::hash_blocking(req.new_password, config.argon2).await?;
+
+        let now = Utc::now();
+        let txn = ctx.db.begin().await?;
+        if !UserTokenRepository::consume(&txn, token.id, now).await? {
+            return Err(AppError::BadRequest(INVALID_TOKEN.to_string()));
+        }
+        let verified_at = user.email_verified_at.unwrap_or(now);
+        let mut model: ActiveModel = user.into();
+        model.password = Set(hashed);
+        model.password_changed_at = Set(Some(now));
+        model.failed_logins = Set(0);
+        model.locked_until = Set(None);
+        model.email_verified_at = Set(Some(verified_at));
+        UserRepository::update(&txn, model).await?;
+        UserTokenRepository::invalidate(&txn, token.user_id, PURPOSE_REFRESH, now).await?;
+        txn.commit().await?;
+        tracing::info!("password of user {} reset", token.user_id);
+        Ok(())
+    }
+
+    async fn send_verification(ctx: &Arc<Context>, user_id: i64, username: &str, email: &str) -> AppResult<()> {
+        let ttl = ctx.reloader.current().account.verify_email_ttl;
+        let token = Self::issue(ctx, user_id, PURPOSE_VERIFY_EMAIL, ttl).await?;
+        let hours = ttl.as_secs().div_ceil(3600).to_string();
+        let link = link(ctx, VERIFY_EMAIL_PATH, &token);
+        EmailOutboxService::enqueue(ctx, email, &verify_email_template(), &[("username", username), ("hours", &hours), ("link", &link)], true).await?;
+        Ok(())
+    }
+
+    /// 生成新的令牌，同一用途之前的令牌失效，返回令牌明文
+    async fn issue(ctx: &Arc<Context>, user_id: i64, purpose: &str, ttl: Duration) -> AppResult<String> {
+        let token = random_token();
+        let now = Utc::now();
+        let model = user_token::ActiveModel {
+            user_id: Set(user_id),
+            purpose: Set(purpose.to_string()),
+            token_hash: Set(digest(&token)),
+            expires_at: Set(now + ttl),
+            used_at: Set(None),
+            created_at: Set(now),
+            ..Default::default()
+        };
+        UserTokenRepository::issue(ctx.db.as_ref(), model, now).await?;
+        Ok(token)
+    }
+
+    async fn find_token(ctx: &Arc<Context>, purpose: &str, token: &str) -> AppResult<(user_token::Model, User)> {
+        let invalid = || AppError::BadRequest(INVALID_TOKEN.to_string());
+        let token = UserTokenRepository::find_valid(&ctx.db, purpose, &digest(token.trim()), Utc::now()).await?.ok_or_else(invalid)?;
+        let user = UserRepository::find_by_id(&ctx.db, token.user_id).await?.ok_or_else(invalid)?;
+        Ok((token, user))
+    }
+}
+
+/// 邮件中的链接，基于`openapi.server`
+fn link(ctx: &Context, path: &str, token: &str) -> String {
+    format!("{}{}/{}?token={}", ctx.config.openapi.server.trim_end_matches('/'), ctx.config.path(), path, token)
+}
+
+/// 注册
+#[derive(Deserialize, ToSchema)]
+pub struct Signup {
+    /// 用户名，3~64位字母、数字及`_.-`
+    pub username: String,
+    pub email: String,
+    pub phone: String,
+    /// 需要满足密码策略
+    pub password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct EmailRequest {
+    pub email: String,
+}
+
+/// 验证邮箱
+#[derive(Deserialize, ToSchema)]
+pub struct VerifyEmail {
+    /// 邮件中的令牌
+    pub token: String,
+}
+
+/// 重置密码
+#[derive(Deserialize, ToSchema)]
+pub struct ResetPassword {
+    /// 邮件中的令牌
+    pub token: String,
+    /// 需要满足密码策略
+    pub new_password: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use salvo::http::StatusCode;
+    use salvo::test::{ResponseExt, TestClient};
+    use sea_orm::EntityTrait;
+
+    use super::*;
+    use crate::service::auth_service::{AuthService, Login, RefreshToken};
+    use crate::test_support::{assert_err, assert_ok, TestApp, BASE_URL};
+
+    /// 发送发件箱中的邮件后，返回最后一封邮件中的令牌
+    async fn last_token(app: &TestApp) -> String {
+        let email = app.sent_emails().await.pop().unwrap();
+        let (_, token) = email.text.split_once("?token=").unwrap();
+        token.chars().take_while(char::is_ascii_hexdigit).collect()
+    }
+
+    fn login(password: &str) -> Login {
+        Login { username: "alice".to_string(), password: password.to_string() }
+    }
+
+    #[tokio::test]
+    async fn test_verify_and_reset() {
+        let app = TestApp::with_config(|config| config.account.enable_signup = true).await;
+        let ctx = &app.ctx;
+
+        let signup = Signup { username: "alice".into(), email: "alice@example.com".into(), phone: "13800000001".into(), password: "Secret-123".into() };
+        let alice = AccountService::signup(ctx, signup).await.unwrap();
+        assert!(app.emails.emails().is_empty());
+        let email = &app.sent_emails().await[0];
+        assert_eq!((email.to.as_slice(), email.subject.as_str()), (["alice@example.com".to_string()].as_slice(), "验证邮箱"));
+        assert!(email.text.contains("http://localhost:8080/email/verify?token="));
+        assert!(email.html.as_deref().unwrap().contains("<a href=\"http://localhost:8080/email/verify?token="));
+        assert_eq!(AuthService::login(ctx, login("Secret-123")).await.unwrap_err().to_string(), "email is not verified");
+
+        // 重新发送后之前的令牌失效，令牌只能使用一次
+        let first = last_token(&app).await;
+        AccountService::resend_verification(ctx, EmailRequest { email: "alice@example.com".into() }).await.unwrap();
+        let second = last_token(&app).await;
+        assert!(AccountService::verify_email(ctx, &first).await.is_err());
+        AccountService::verify_email(ctx, &second).await.unwrap();
+        assert!(AccountService::verify_email(ctx, &second).await.is_err());
+        let tokens = AuthService::login(ctx, login("Secret-123")).await.unwrap();
+
+        // 不存在的邮箱同样返回成功
+        AccountService::forgot_password(ctx, EmailRequest { email: "nobody@example.com".into() }).await.unwrap();
+        assert_eq!(app.sent_emails().await.len(), 2);
+        AccountService::forgot_password(ctx, EmailRequest { email: "alice@example.com".into() }).await.unwrap();
+        let token = last_token(&app).await;
+        let reset = |password: &str| ResetPassword { token: token.clone(), new_password: password.to_string() };
+        assert!(AccountService::reset_password(ctx, reset("short")).await.is_err());
+        AccountService::reset_password(ctx, reset("Changed-456")).await.unwrap();
+        assert_eq!(AccountService::reset_password(ctx, reset("Changed-789")).await.unwrap_err().to_string(), "invalid or expired token");
+        assert!(AuthService::login(ctx, login("Secret-123")).await.is_err());
+        assert!(AuthService::login(ctx, login("Changed-456")).await.is_ok());
+        // 重置密码前签发的刷新令牌失效
+        assert!(AuthService::refresh(ctx, RefreshToken { refresh_token: tokens.refresh_token }).await.is_err());
+
+        // 令牌过期
+        AccountService::forgot_password(ctx, EmailRequest { email: "alice@example.com".into() }).await.unwrap();
+        let expired = last_token(&app).await;
+        user_token::Entity::update_many()
+            .col_expr(user_token::Column::ExpiresAt, sea_orm::sea_query::Expr::value(Utc::now()))
+            .exec(ctx.db.as_ref())
+            .await
+            .unwrap();
+        assert!(AccountService::reset_password(ctx, ResetPassword { token: expired, new_password: "Changed-789".into() }).await.is_err());
+        assert_eq!(UserService::find_by_id(ctx, alice.id).await.unwrap().email, "alice@example.com");
+    }
+
+    /// 邮件中的链接打开页面，不消耗令牌，页面再提交令牌
+    #[tokio::test]
+    async fn test_links() {
+        let app = TestApp::with_config(|config| config.account.enable_signup = true).await;
+        let service = app.service();
+        let signup = Signup { username: "alice".into(), email: "alice@example.com".into(), phone: "13800000001".into(), password: "Secret-123".into() };
+        AccountService::signup(&app.ctx, signup).await.unwrap();
+        let token = last_token(&app).await;
+
+        for path in ["email/verify", "password/reset"] {
+            let mut res = TestClient::get(format!("{}/{}?token={}", BASE_URL, path, token)).send(&service).await;
+            assert_eq!(res.content_type().map(|c| c.essence_str().to_string()).as_deref(), Some("text/html"));
+            assert!(res.take_string().await.unwrap().contains("method: \"POST\""));
+        }
+        let mut res = TestClient::post(format!("{}/email/verify", BASE_URL)).json(&serde_json::json!({"token": token})).send(&service).await;
+        assert_ok::<()>(&mut res).await;
+        assert!(AuthService::login(&app.ctx, login("Secret-123")).await.is_ok());
+
+        // 令牌只能使用一次
+        let mut res = TestClient::post(format!("{}/email/verify", BASE_URL)).json(&serde_json::json!({"token": token})).send(&service).await;
+        assert_err(&mut res, StatusCode::BAD_REQUEST, INVALID_TOKEN).await;
+        let body = serde_json::json!({"token": "0".repeat(64), "new_password": "Changed-456"});
+        let mut res = TestClient::post(format!("{}/password/reset", BASE_URL)).json(&body).send(&service).await;
+        assert_err(&mut res, StatusCode::BAD_REQUEST, INVALID_TOKEN).await;
+    }
+}
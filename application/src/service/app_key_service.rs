@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use salvo::oapi::ToSchema;
+use sea_orm::prelude::DateTimeUtc;
+use sea_orm::sqlx::types::chrono::Utc;
+use sea_orm::Set;
+use serde::{Deserialize, Serialize};
+
+use common::domain::app_key::app_key::{ActiveModel, Model as AppKey};
+use common::domain::app_key::AppKeyRepository;
+
+use crate::auth::{digest, random_token, split_scopes, Principal, PrincipalKind};
+use crate::core::context::Context;
+use crate::core::errors::{AppError, AppResult};
+
+/// 保存用于识别密钥的前几位
+const KEY_PREFIX_LEN: usize = 8;
+const MAX_APP_NAME_LEN: usize = 64;
+
+pub struct AppKeyService;
+
+impl AppKeyService {
+    /// 创建密钥，明文只在创建时返回一次
+    #[tracing::instrument(skip(ctx))]
+    pub(crate) async fn create(ctx: &Arc<Context>, req: CreateAppKey) -> AppResult<CreatedAppKey> {
+        let app_name = req.app_name.trim();
+        if app_name.is_empty() || app_name.len() > MAX_APP_NAME_LEN {
+            return Err(AppError::BadRequest(format!("app_name must be 1 to {} characters", MAX_APP_NAME_LEN)));
+        }
+        if req.scopes.iter().any(|s| s.is_empty() || s.contains(char::is_whitespace)) {
+            return Err(AppError::BadRequest("scopes must not be empty or contain whitespace".to_string()));
+        }
+        let now = Utc::now();
+        if req.expires_at.is_some_and(|t| t <= now) {
+            return Err(AppError::BadRequest("expires_at must be in the future".to_string()));
+        }
+
+        let key = random_token();
+        let model = ActiveModel {
+            app_name: Set(app_name.to_string()),
+            key_prefix: Set(key[..KEY_PREFIX_LEN].to_string()),
+            key_hash: Set(digest(&key)),
+            scopes: Set(req.scopes.join(" ")),
+            expires_at: Set(req.expires_at),
+            revoked_at: Set(None),
+            created_at: Set(now),
+            ..Default::default()
+        };
+        let model = AppKeyRepository::insert(&ctx.db, model).await?;
+        tracing::info!("app key {} created for {}", model.id, model.app_name);
+        Ok(CreatedAppKey { key, app_key: model.into() })
+    }
+
+    pub(crate) async fn list(ctx: &Arc<Context>) -> AppResult<Vec<AppKeyVo>> {
+        let keys = AppKeyRepository::find_all(&ctx.db).await?;
+        Ok(keys.into_iter().map(Into::into).collect())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    pub(crate) async fn revoke(ctx: &Arc<Context>, id: i64) -> AppResult<AppKeyVo> {
+        match AppKeyRepository::revoke(&ctx.db, id, Utc::now()).await? {
+            None => Err(AppError::NotFound(format!("can not find app key with id: {}", id))),
+            Some(model) => {
+                tracing::info!("app key {} of {} revoked", model.id, model.app_name);
+                Ok(model.into())
+            }
+        }
+    }
+
+    /// 校验`APP-TOKEN`，返回调用的应用
+    pub(crate) async fn authenticate(ctx: &Arc<Context>, key: &str) -> AppResult<Principal> {
+        let Some(model) = AppKeyRepository::find_by_hash(&ctx.db, &digest(key)).await? else {
+            return Err(AppError::Unauthorized("invalid app token"));
+        };
+        if model.revoked_at.is_some() {
+            return Err(AppError::Unauthorized("app token revoked"));
+        }
+        if model.expires_at.is_some_and(|t| t <= Utc::now()) {
+            return Err(AppError::Unauthorized("app token expired"));
+        }
+        Ok(Principal {
+            kind: PrincipalKind::App,
+            id: model.id.to_string(),
+            name: model.app_name,
+            scopes: split_scopes(&model.scopes),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateAppKey {
+    /// 应用名称，记录在日志中
+    pub app_name: String,
+    /// 权限范围
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// 过期时间，为空时不过期
+    pub expires_at: Option<DateTimeUtc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreatedAppKey {
+    /// 密钥明文，只返回一次
+    pub key: String,
+    pub app_key: AppKeyVo,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AppKeyVo {
+    pub id: i64,
+    pub app_name: String,
+    /// 密钥的前几位
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTimeUtc>,
+    pub revoked_at: Option<DateTimeUtc>,
+    pub created_at: DateTimeUtc,
+}
+
+impl From<AppKey> for AppKeyVo {
+    fn from(value: AppKey) -> Self {
+        Self {
+            id: value.id,
+            app_name: value.app_name,
+            key_prefix: value.key_prefix,
+            scopes: split_scopes(&value.scopes),
+            expires_at: value.expires_at,
+            revoked_at: value.revoked_at,
+            created_at: value.created_at,
+        }
+    }
+}
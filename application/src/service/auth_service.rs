@@ -0,0 +1,292 @@
+use std::sync::Arc;
+
+use salvo::oapi::ToSchema;
+use sea_orm::sqlx::types::chrono::Utc;
+use sea_orm::{ConnectionTrait, Set, TransactionTrait};
+use serde::{Deserialize, Serialize};
+
+use common::domain::user::user::{ActiveModel, Model as User};
+use common::domain::user::user_token::{self, PURPOSE_REFRESH};
+use common::domain::user::{UserRepository, UserTokenRepository};
+
+use crate::auth::jwt::JwtIssuer;
+use crate::auth::{digest, password, random_token, Principal, PrincipalKind};
+use crate::core::context::Context;
+use crate::core::errors::{AppError, AppResult};
+
+/// 用户名或密码错误时不区分具体原因
+const INVALID_CREDENTIALS: &str = "invalid username or password";
+
+pub struct AuthService;
+
+impl AuthService {
+    /// 用户名密码登录，连续失败达到配置次数后锁定，哈希参数变化时重新哈希
+    #[tracing::instrument(skip_all, fields(username = %req.username))]
+    pub(crate) async fn login(ctx: &Arc<Context>, req: Login) -> AppResult<Tokens> {
+        let issuer = Self::issuer(ctx)?;
+        let config = ctx.reloader.current().password.clone();
+        let user = UserRepository::find_by_username(&ctx.db, req.username.trim()).await?;
+        // 用户不存在或已锁定时同样计算一次哈希并返回相同的错误，避免判断用户是否存在
+        let Some(user) = user.filter(|u| Self::check_locked(u).is_ok()) else {
+            password::hash_blocking(req.password, config.argon2).await?;
+            return Err(AppError::Unauthorized(INVALID_CREDENTIALS));
+        };
+        if !Self::verify(ctx, &user, req.password.clone()).await? {
+            return Err(AppError::Unauthorized(INVALID_CREDENTIALS));
+        }
+        if ctx.reloader.current().account.require_verified_email && user.email_verified_at.is_none() {
+            return Err(AppError::Unauthorized("email is not verified"));
+        }
+
+        let mut model: ActiveModel = user.clone().into();
+        let mut changed = false;
+        if user.failed_logins != 0 || user.locked_until.is_some() {
+            model.failed_logins = Set(0);
+            model.locked_until = Set(None);
+            changed = true;
+        }
+        if password::needs_rehash(&user.password, &config.argon2) {
+            model.password = Set(password::hash_blocking(req.password, config.argon2.clone()).await?);
+            changed = true;
+            tracing::info!("password of user {} rehashed with the current parameters", user.id);
+        }
+        if changed {
+            UserRepository::update(ctx.db.as_ref(), model).await?;
+        }
+        Self::tokens(ctx.db.as_ref(), issuer, &user).await
+    }
+
+    /// 使用刷新令牌换取新的令牌，刷新令牌只能使用一次，修改密码后之前签发的刷新令牌失效
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn refresh(ctx: &Arc<Context>, req: RefreshToken) -> AppResult<Tokens> {
+        let issuer = Self::issuer(ctx)?;
+        let verifier = ctx.jwt.as_deref().ok_or(AppError::Unauthorized("authentication is not configured"))?;
+        let claims = verifier.verify_refresh(&req.refresh_token)?;
+        let jti = claims.jti.as_deref().ok_or(AppError::Unauthorized("invalid token"))?;
+        let token = UserTokenRepository::find_valid(&ctx.db, PURPOSE_REFRESH, &digest(jti), Utc::now()).await?;
+        let token = token.filter(|t| t.user_id.to_string() == claims.sub).ok_or(AppError::Unauthorized("token revoked"))?;
+        let user = UserRepository::find_by_id(&ctx.db, token.user_id).await?.ok_or(AppError::Unauthorized("invalid token"))?;
+        Self::check_locked(&user)?;
+        let txn = ctx.db.begin().await?;
+        // 并发使用同一刷新令牌时只有一个成功
+        if !UserTokenRepository::consume(&txn, token.id, Utc::now()).await? {
+            return Err(AppError::Unauthorized("token revoked"));
+        }
+        let tokens = Self::tokens(&txn, issuer, &user).await?;
+        txn.commit().await?;
+        Ok(tokens)
+    }
+
+    /// 修改当前用户的密码，返回新的令牌，之前签发的刷新令牌失效
+    #[tracing::instrument(skip_all, fields(user = %principal.id))]
+    pub(crate) async fn change_password(ctx: &Arc<Context>, principal: &Principal, req: ChangePassword) -> AppResult<Tokens> {
+        let issuer = Self::issuer(ctx)?;
+        let user_id = match principal.kind {
+            PrincipalKind::User => principal.id.parse::<i64>().ok(),
+            PrincipalKind::App => None,
+        };
+        let user = match user_id {
+            Some(id) => UserRepository::find_by_id(&ctx.db, id).await?,
+            None => None,
+        };
+        let user = user.ok_or_else(|| AppError::Forbidden("only local users can change password".to_string()))?;
+        Self::check_locked(&user)?;
+        if !Self::verify(ctx, &user, req.current_password.clone()).await? {
+            return Err(AppError::BadRequest("current password is incorrect".to_string()));
+        }
+        if req.new_password == req.current_password {
+            return Err(AppError::BadRequest("new password must be different from the current one".to_string()));
+        }
+        let config = ctx.reloader.current().password.clone();
+        config.check(&req.new_password, &user.username)?;
+        let hashed = password::hash_blocking(req.new_password, config.argon2).await?;
+
+        let now = Utc::now();
+        let mut model: ActiveModel = user.into();
+        model.password = Set(hashed);
+        model.password_changed_at = Set(Some(now));
+        model.failed_logins = Set(0);
+        let txn = ctx.db.begin().await?;
+        let user = UserRepository::update(&txn, model).await?;
+        UserTokenRepository::invalidate(&txn, user.id, PURPOSE_REFRESH, now).await?;
+        let tokens = Self::tokens(&txn, issuer, &user).await?;
+        txn.commit().await?;
+        tracing::info!("password of user {} changed", user.id);
+        Ok(tokens)
+    }
+
+    fn issuer(ctx: &Arc<Context>) -> AppResult<&JwtIssuer> {
+        ctx.jwt_issuer.as_deref().ok_or(AppError::Unauthorized("login is not configured"))
+    }
+
+    fn check_locked(user: &User) -> AppResult<()> {
+        if user.locked_until.is_some_and(|t| t > Utc::now()) {
+            return Err(AppError::Unauthorized("account is locked, try again later"));
+        }
+        Ok(())
+    }
+
+    /// 校验密码，失败时累计次数，达到配置次数后锁定
+    async fn verify(ctx: &Arc<Context>, user: &User, input: String) -> AppResult<bool> {
+        if password::verify_blocking(input, user.password.clone()).await? {
+            return Ok(true);
+        }
+        let config = ctx.reloader.current().password.clone();
+        let failures = UserRepository::increase_failed_logins(&ctx.db, user.id).await?;
+        if config.max_failures > 0 && failures >= config.max_failures as i32 {
+            UserRepository::lock(&ctx.db, user.id, Utc::now() + config.lockout).await?;
+            tracing::warn!("user {} locked for {:?} after {} failed logins", user.id, config.lockout, failures);
+        }
+        Ok(false)
+    }
+
+    /// 签发访问令牌及刷新令牌，保存刷新令牌`jti`的摘要
+    async fn tokens<C: ConnectionTrait>(db: &C, issuer: &JwtIssuer, user: &User) -> AppResult<Tokens> {
+        let sub = user.id.to_string();
+        let jti = random_token();
+        let now = Utc::now();
+        let model = user_token::ActiveModel {
+            user_id: Set(user.id),
+            purpose: Set(PURPOSE_REFRESH.to_string()),
+            token_hash: Set(digest(&jti)),
+            expires_at: Set(now + issuer.refresh_ttl),
+            used_at: Set(None),
+            created_at: Set(now),
+            ..Default::default()
+        };
+        UserTokenRepository::save(db, model).await?;
+        Ok(Tokens {
+            access_token: issuer.access_token(&sub, &user.username)?,
+            refresh_token: issuer.refresh_token(&sub, &user.username, &jti)?,
+            token_type: "Bearer",
+            expires_in: issuer.access_ttl.as_secs(),
+        })
+    }
+}
+
+/// 用户名密码登录
+#[derive(Deserialize, ToSchema)]
+pub struct Login {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RefreshToken {
+    pub refresh_token: String,
+}
+
+/// 修改密码
+#[derive(Deserialize, ToSchema)]
+pub struct ChangePassword {
+    pub current_password: String,
+    /// 需要满足密码策略
+    pub new_password: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Tokens {
+    pub access_token: String,
+    /// 只能用于换取新的令牌
+    pub refresh_token: String,
+    pub token_type: &'static str,
+    /// 访问令牌的有效期（秒）
+    pub expires_in: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common::domain::user::user;
+    use sea_orm::{ActiveModelTrait, EntityTrait};
+
+    use super::*;
+    use crate::auth::password::tests::weak;
+    use crate::auth::password::Argon2Config;
+    use crate::test_support::TestApp;
+
+    fn login(username: &str, password: &str) -> Login {
+        Login { username: username.to_string(), password: password.to_string() }
+    }
+
+    #[tokio::test]
+    async fn test_login() {
+        let app = TestApp::with_config(|config| {
+            config.password.max_failures = 3;
+            config.password.lockout = Duration::from_millis(1500);
+        })
+        .await;
+        let ctx = &app.ctx;
+        // 旧参数哈希的密码登录后重新哈希
+        let old = password::hash("Secret-123", &Argon2Config { iterations: 2, ..weak() }).unwrap();
+        let alice = user::ActiveModel {
+            username: Set("alice".to_string()),
+            password: Set(old.clone()),
+            email: Set("alice@example.com".to_string()),
+            phone: Set("13800000001".to_string()),
+            email_verified_at: Set(Some(Utc::now())),
+            ..Default::default()
+        }
+        .insert(ctx.db.as_ref())
+        .await
+        .unwrap();
+        let stored = || async { user::Entity::find_by_id(alice.id).one(ctx.db.as_ref()).await.unwrap().unwrap() };
+
+        let tokens = AuthService::login(ctx, login(" alice ", "Secret-123")).await.unwrap();
+        let verifier = ctx.jwt.as_deref().unwrap();
+        assert_eq!(verifier.verify(&tokens.access_token).unwrap().sub, alice.id.to_string());
+        assert!(verifier.verify(&tokens.refresh_token).is_err());
+        assert!(!password::needs_rehash(&stored().await.password, &weak()));
+
+        // 使用哈希前保存的明文密码登录后改为哈希保存
+        let bob = user::ActiveModel {
+            username: Set("bob".to_string()),
+            password: Set("Plain-123".to_string()),
+            email: Set("bob@example.com".to_string()),
+            phone: Set("13800000002".to_string()),
+            email_verified_at: Set(Some(Utc::now())),
+            ..Default::default()
+        }
+        .insert(ctx.db.as_ref())
+        .await
+        .unwrap();
+        AuthService::login(ctx, login("bob", "Plain-123")).await.unwrap();
+        let hashed = user::Entity::find_by_id(bob.id).one(ctx.db.as_ref()).await.unwrap().unwrap().password;
+        assert!(password::verify("Plain-123", &hashed) && !password::needs_rehash(&hashed, &weak()));
+
+        let refresh = |token: &str| AuthService::refresh(ctx, RefreshToken { refresh_token: token.to_string() });
+        let refreshed = refresh(&tokens.refresh_token).await.unwrap();
+        assert_eq!(verifier.verify(&refreshed.access_token).unwrap().name.as_deref(), Some("alice"));
+        assert_eq!(refresh(&tokens.access_token).await.unwrap_err().to_string(), "invalid token type");
+        // 刷新令牌只能使用一次
+        assert_eq!(refresh(&tokens.refresh_token).await.unwrap_err().to_string(), "token revoked");
+        let other = AuthService::login(ctx, login("alice", "Secret-123")).await.unwrap();
+
+        // 修改密码后之前签发的刷新令牌都失效
+        let principal = Principal::from(verifier.verify(&tokens.access_token).unwrap());
+        let change = |current: &str, new: &str| ChangePassword { current_password: current.to_string(), new_password: new.to_string() };
+        assert!(matches!(AuthService::change_password(ctx, &principal, change("Secret-123", "short")).await, Err(AppError::BadRequest(_))));
+        let error = AuthService::change_password(ctx, &principal, change("wrong", "Changed-456")).await.unwrap_err();
+        assert!(matches!(error, AppError::BadRequest(_)), "{}", error);
+        let changed = AuthService::change_password(ctx, &principal, change("Secret-123", "Changed-456")).await.unwrap();
+        for token in [&refreshed.refresh_token, &other.refresh_token] {
+            assert_eq!(refresh(token).await.unwrap_err().to_string(), "token revoked");
+        }
+        assert!(refresh(&changed.refresh_token).await.is_ok());
+
+        // 连续失败后锁定，锁定期间正确的密码也不能登录
+        for _ in 0..3 {
+            assert_eq!(AuthService::login(ctx, login("alice", "wrong")).await.unwrap_err().to_string(), INVALID_CREDENTIALS);
+        }
+        // 锁定时与密码错误的提示相同，不提示用户是否存在
+        let error = AuthService::login(ctx, login("alice", "Changed-456")).await.unwrap_err();
+        assert_eq!(error.to_string(), INVALID_CREDENTIALS);
+        assert_eq!(stored().await.failed_logins, 0);
+        tokio::time::sleep(Duration::from_millis(1600)).await;
+        assert!(AuthService::login(ctx, login("alice", "Changed-456")).await.is_ok());
+        assert!(stored().await.locked_until.is_none());
+
+        assert_eq!(AuthService::login(ctx, login("nobody", "Changed-456")).await.unwrap_err().to_string(), INVALID_CREDENTIALS);
+    }
+}
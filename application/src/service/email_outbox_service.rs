@@ -0,0 +1,256 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use salvo::oapi::ToSchema;
+use sea_orm::prelude::DateTimeUtc;
+use sea_orm::sqlx::types::chrono::Utc;
+use sea_orm::Set;
+use serde::Serialize;
+use tokio::sync::watch;
+
+use common::domain::email_outbox::email_outbox::{ActiveModel, Model as Outbox, STATUS_DEAD, STATUS_PENDING};
+use common::domain::email_outbox::EmailOutboxRepository;
+use common::email::{Rendered, Template};
+
+use crate::core::context::Context;
+use crate::core::errors::{AppError, AppResult};
+use crate::core::shutdown;
+
+/// 认领后发送的最长时间，超过后其他实例可以重新发送
+const CLAIM_LEASE: Duration = Duration::from_secs(5 * 60);
+/// 管理接口最多返回的数量
+const MAX_DEAD_LIST: u64 = 100;
+
+pub struct EmailOutboxService;
+
+impl EmailOutboxService {
+    /// 渲染模板后保存到发件箱，由后台任务发送，返回邮件ID；
+    /// 正文包含一次性令牌等时`sensitive`为`true`，多次失败后清空正文，不能重新发送
+    pub(crate) async fn enqueue(ctx: &Arc<Context>, to: &str, template: &Template, vars: &[(&str, &str)], sensitive: bool) -> AppResult<i64> {
+        let rendered = template.render(vars)?;
+        // 提前校验收件人等，避免无法生成的邮件反复重试
+        ctx.email.build(to, rendered.clone())?;
+        let now = Utc::now();
+        let model = ActiveModel {
+            recipient: Set(to.to_string()),
+            subject: Set(rendered.subject),
+            text_body: Set(rendered.text),
+            html_body: Set(rendered.html),
+            sensitive: Set(sensitive),
+            status: Set(STATUS_PENDING.to_string()),
+            attempts: Set(0),
+            next_attempt_at: Set(now),
+            last_error: Set(None),
+            created_at: Set(now),
+            ..Default::default()
+        };
+        Ok(EmailOutboxRepository::insert(&ctx.db, model).await?.id)
+    }
+
+    /// 发送到期的邮件，返回本次处理的数量，失败的按指数退避重试，超过次数后标记为`dead`
+    pub(crate) async fn deliver_due(ctx: &Arc<Context>) -> AppResult<usize> {
+        let config = ctx.reloader.current().email_outbox.clone();
+        let now = Utc::now();
+        let mut processed = 0;
+        for outbox in EmailOutboxRepository::find_due(&ctx.db, now, config.batch_size).await? {
+            if !EmailOutboxRepository::claim(&ctx.db, outbox.id, now, now + CLAIM_LEASE).await? {
+                continue;
+            }
+            processed += 1;
+            let rendered = Rendered { subject: outbox.subject, text: outbox.text_body, html: outbox.html_body };
+            let Err(e) = ctx.email.send_rendered(&outbox.recipient, rendered).await else {
+                EmailOutboxRepository::delete(&ctx.db, outbox.id).await?;
+                continue;
+            };
+            let attempts = outbox.attempts.saturating_add(1);
+            let dead = attempts as u32 >= config.max_attempts;
+            let next_attempt_at = Utc::now() + config.backoff(attempts as u32);
+            EmailOutboxRepository::fail(&ctx.db, outbox.id, attempts, next_attempt_at, &e.to_string(), dead).await?;
+            if dead && outbox.sensitive {
+                EmailOutboxRepository::discard_body(&ctx.db, outbox.id).await?;
+            }
+            if dead {
+                tracing::error!("email {} is dead after {} attempts: {}", outbox.id, attempts, e);
+            } else {
+                tracing::warn!("send email {} fail ({} attempts), retry at {}: {}", outbox.id, attempts, next_attempt_at, e);
+            }
+        }
+        Ok(processed)
+    }
+
+    /// 多次失败的邮件
+    pub(crate) async fn list_dead(ctx: &Arc<Context>) -> AppResult<Vec<OutboxVo>> {
+        Ok(EmailOutboxRepository::find_dead(&ctx.db, MAX_DEAD_LIST).await?.into_iter().map(Into::into).collect())
+    }
+
+    /// 重新发送`dead`的邮件，已清空正文的邮件需要用户重新申请
+    #[tracing::instrument(skip(ctx))]
+    pub(crate) async fn retry(ctx: &Arc<Context>, id: i64) -> AppResult<OutboxVo> {
+        let not_found = || AppError::NotFound(format!("can not find dead email with id: {}", id));
+        match EmailOutboxRepository::find_by_id(&ctx.db, id).await? {
+            Some(outbox) if outbox.status == STATUS_DEAD && outbox.sensitive => {
+                return Err(AppError::Conflict(format!("email {} contains one-time tokens and can not be retried", id)));
+            }
+            Some(outbox) if outbox.status == STATUS_DEAD => {}
+            _ => return Err(not_found()),
+        }
+        if !EmailOutboxRepository::retry(&ctx.db, id, Utc::now()).await? {
+            return Err(not_found());
+        }
+        let outbox = EmailOutboxRepository::find_by_id(&ctx.db, id).await?.ok_or_else(not_found)?;
+        tracing::info!("dead email {} will be retried", id);
+        Ok(outbox.into())
+    }
+
+    /// 启动后台发送任务，关闭时在`drain_timeout`内发送完到期的邮件
+    pub(crate) async fn start(ctx: Arc<Context>) {
+        let (stop_tx, stop_rx) = watch::channel(false);
+        let handle = tokio::spawn(Self::run(ctx, stop_rx));
+        shutdown::push(async move {
+            let _ = stop_tx.send(true);
+            let _ = handle.await;
+        })
+        .await;
+    }
+
+    async fn run(ctx: Arc<Context>, mut stop: watch::Receiver<bool>) {
+        loop {
+            if *stop.borrow() {
+                break;
+            }
+            if let Err(e) = Self::deliver_due(&ctx).await {
+                tracing::warn!("deliver emails fail: {}", e);
+            }
+            let interval = ctx.reloader.current().email_outbox.poll_interval;
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = stop.changed() => {}
+            }
+        }
+        let timeout = ctx.reloader.current().email_outbox.drain_timeout;
+        if tokio::time::timeout(timeout, Self::drain(&ctx)).await.is_err() {
+            tracing::warn!("email outbox is not drained in {:?}, the rest will be sent after restart", timeout);
+        }
+    }
+
+    /// 发送所有到期的邮件，失败的邮件推迟到之后，不会重复处理
+    async fn drain(ctx: &Arc<Context>) {
+        loop {
+            match Self::deliver_due(ctx).await {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!("drain email outbox fail: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// 发件箱中的邮件，不包含正文
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OutboxVo {
+    pub id: i64,
+    pub recipient: String,
+    pub subject: String,
+    /// 包含一次性令牌，`dead`后不能重新发送
+    pub sensitive: bool,
+    /// `pending`/`dead`
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTimeUtc,
+    pub last_error: Option<String>,
+    pub created_at: DateTimeUtc,
+}
+
+impl From<Outbox> for OutboxVo {
+    fn from(value: Outbox) -> Self {
+        Self {
+            id: value.id,
+            recipient: value.recipient,
+            subject: value.subject,
+            sensitive: value.sensitive,
+            status: value.status,
+            attempts: value.attempts,
+            next_attempt_at: value.next_attempt_at,
+            last_error: value.last_error,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::domain::email_outbox::email_outbox;
+    use sea_orm::EntityTrait;
+
+    use salvo::http::StatusCode;
+
+    use super::*;
+    use crate::test_support::TestApp;
+
+    /// 到期时间提前，模拟退避时间已过
+    async fn expire(ctx: &Context) {
+        email_outbox::Entity::update_many()
+            .col_expr(email_outbox::Column::NextAttemptAt, sea_orm::sea_query::Expr::value(Utc::now()))
+            .exec(ctx.db.as_ref())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_deliver() {
+        let app = TestApp::with_config(|config| config.email_outbox.max_attempts = 2).await;
+        let ctx = &app.ctx;
+        let template = Template::new("你好 {{name}}", "正文");
+        assert!(EmailOutboxService::enqueue(ctx, "not an address", &template, &[("name", "Alice")], false).await.is_err());
+        assert!(EmailOutboxService::enqueue(ctx, "alice@example.com", &template, &[], false).await.is_err());
+
+        // 失败后按退避时间重试，超过次数后标记为dead
+        app.emails.fail_next(2);
+        let id = EmailOutboxService::enqueue(ctx, "alice@example.com", &template, &[("name", "Alice")], false).await.unwrap();
+        assert_eq!(EmailOutboxService::deliver_due(ctx).await.unwrap(), 1);
+        let outbox = EmailOutboxRepository::find_by_id(&ctx.db, id).await.unwrap().unwrap();
+        assert_eq!((outbox.status.as_str(), outbox.attempts), (STATUS_PENDING, 1));
+        assert!(outbox.next_attempt_at > Utc::now() + Duration::from_secs(20));
+        assert_eq!(outbox.last_error.as_deref(), Some("send email fail: connection refused"));
+        assert_eq!(EmailOutboxService::deliver_due(ctx).await.unwrap(), 0);
+        expire(ctx).await;
+        assert_eq!(EmailOutboxService::deliver_due(ctx).await.unwrap(), 1);
+        let dead = EmailOutboxService::list_dead(ctx).await.unwrap();
+        assert_eq!((dead.len(), dead[0].status.as_str(), dead[0].attempts), (1, STATUS_DEAD, 2));
+        expire(ctx).await;
+        assert_eq!(EmailOutboxService::deliver_due(ctx).await.unwrap(), 0);
+
+        // 重新发送dead的邮件，成功后删除
+        assert_eq!(EmailOutboxService::retry(ctx, id).await.unwrap().attempts, 0);
+        assert!(EmailOutboxService::retry(ctx, id).await.is_err_and(|e| e.status_code() == StatusCode::NOT_FOUND));
+        assert_eq!(EmailOutboxService::deliver_due(ctx).await.unwrap(), 1);
+        assert!(EmailOutboxRepository::find_by_id(&ctx.db, id).await.unwrap().is_none());
+        assert_eq!(app.emails.emails()[0].subject, "你好 Alice");
+
+        // 包含令牌的邮件dead后清空正文，不能重新发送
+        app.emails.fail_next(2);
+        let id = EmailOutboxService::enqueue(ctx, "alice@example.com", &template, &[("name", "Alice")], true).await.unwrap();
+        EmailOutboxService::deliver_due(ctx).await.unwrap();
+        expire(ctx).await;
+        EmailOutboxService::deliver_due(ctx).await.unwrap();
+        let outbox = EmailOutboxRepository::find_by_id(&ctx.db, id).await.unwrap().unwrap();
+        assert_eq!((outbox.status.as_str(), outbox.text_body.as_str(), outbox.html_body), (STATUS_DEAD, "", None));
+        let error = EmailOutboxService::retry(ctx, id).await.unwrap_err();
+        assert!(error.to_string().contains("can not be retried"));
+        assert_eq!(error.status_code(), StatusCode::CONFLICT);
+        EmailOutboxRepository::delete(&ctx.db, id).await.unwrap();
+
+        // 关闭时发送完到期的邮件
+        for name in ["Bob", "Carol"] {
+            EmailOutboxService::enqueue(ctx, "bob@example.com", &template, &[("name", name)], false).await.unwrap();
+        }
+        let (stop_tx, stop_rx) = watch::channel(true);
+        EmailOutboxService::run(ctx.clone(), stop_rx).await;
+        drop(stop_tx);
+        assert_eq!(app.emails.emails().len(), 3);
+        assert!(email_outbox::Entity::find().all(ctx.db.as_ref()).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use salvo::oapi::ToSchema;
+use sea_orm::prelude::DateTimeUtc;
+use serde::Serialize;
+
+use common::migration::{MigrationInfo, MigrationReport, MigrationSql};
+
+use crate::core::context::Context;
+use crate::core::errors::AppResult;
+
+pub struct MigrationService;
+
+impl MigrationService {
+    /// 所有迁移的执行状态及与程序的差异
+    pub(crate) async fn report(ctx: &Arc<Context>) -> AppResult<MigrationReportVo> {
+        Ok(common::migration::report(&ctx.db).await?.into())
+    }
+
+    /// 未执行的迁移将执行的SQL
+    pub(crate) async fn dry_run(ctx: &Arc<Context>) -> AppResult<Vec<MigrationSqlVo>> {
+        Ok(common::migration::dry_run(&ctx.db).await?.into_iter().map(Into::into).collect())
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MigrationReportVo {
+    pub migrations: Vec<MigrationVo>,
+    /// 数据库与程序不一致时的说明，一致时为空
+    pub drift: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MigrationVo {
+    pub name: String,
+    /// `applied`/`pending`/`unknown`，`unknown`表示数据库比程序新
+    pub state: String,
+    pub applied_at: Option<DateTimeUtc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MigrationSqlVo {
+    pub name: String,
+    pub statements: Vec<String>,
+}
+
+impl From<MigrationReport> for MigrationReportVo {
+    fn from(value: MigrationReport) -> Self {
+        Self {
+            drift: value.drift().map(|d| d.to_string()),
+            migrations: value.migrations.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<MigrationInfo> for MigrationVo {
+    fn from(value: MigrationInfo) -> Self {
+        Self { name: value.name, state: value.state.to_string(), applied_at: value.applied_at }
+    }
+}
+
+impl From<MigrationSql> for MigrationSqlVo {
+    fn from(value: MigrationSql) -> Self {
+        Self { name: value.name, statements: value.statements }
+    }
+}
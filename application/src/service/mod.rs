@@ -1 +1,8 @@
-pub(crate) mod user_service;
\ No newline at end of file
+pub(crate) mod account_service;
+pub(crate) mod app_key_service;
+pub(crate) mod auth_service;
+pub(crate) mod email_outbox_service;
+pub(crate) mod migration_service;
+pub(crate) mod permission_service;
+pub(crate) mod seed_service;
+pub(crate) mod user_service;
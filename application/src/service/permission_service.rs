@@ -0,0 +1,71 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use common::domain::user::PermissionRepository;
+
+use crate::auth::{Principal, PrincipalKind};
+use crate::core::context::Context;
+use crate::core::errors::AppResult;
+
+/// 缓存的有效期，其他实例修改的角色在过期后生效
+const CACHE_TTL: Duration = Duration::from_secs(30);
+/// 超过该数量时清理过期的缓存
+const CACHE_PRUNE_SIZE: usize = 1024;
+
+type Permissions = Arc<HashSet<String>>;
+
+/// 用户权限的缓存，避免每个请求都查询数据库；本实例修改用户角色时清除
+#[derive(Default)]
+pub struct PermissionCache {
+    users: RwLock<HashMap<i64, (Instant, Permissions)>>,
+}
+
+impl PermissionCache {
+    fn get(&self, user_id: i64) -> Option<Permissions> {
+        let users = self.users.read().unwrap_or_else(|e| e.into_inner());
+        users.get(&user_id).filter(|(at, _)| at.elapsed() < CACHE_TTL).map(|(_, permissions)| permissions.clone())
+    }
+
+    fn put(&self, user_id: i64, permissions: Permissions) {
+        let mut users = self.users.write().unwrap_or_else(|e| e.into_inner());
+        if users.len() >= CACHE_PRUNE_SIZE {
+            users.retain(|_, (at, _)| at.elapsed() < CACHE_TTL);
+        }
+        users.insert(user_id, (Instant::now(), permissions));
+    }
+
+    /// 用户的角色变化后清除
+    pub fn evict(&self, user_id: i64) {
+        self.users.write().unwrap_or_else(|e| e.into_inner()).remove(&user_id);
+    }
+
+    /// 角色的权限变化后清除所有用户
+    pub fn clear(&self) {
+        self.users.write().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+pub struct PermissionService;
+
+impl PermissionService {
+    /// 调用方拥有的权限：用户为所有角色的权限，外部应用为密钥的权限范围
+    pub(crate) async fn granted(ctx: &Arc<Context>, principal: &Principal) -> AppResult<Permissions> {
+        match principal.kind {
+            PrincipalKind::App => Ok(Arc::new(principal.scopes.iter().cloned().collect())),
+            PrincipalKind::User => {
+                // 非数字的用户标识不会有角色
+                let Ok(user_id) = principal.id.parse::<i64>() else {
+                    return Ok(Arc::default());
+                };
+                if let Some(permissions) = ctx.permissions.get(user_id) {
+                    return Ok(permissions);
+                }
+                let permissions = PermissionRepository::find_by_user(&ctx.db, user_id).await?;
+                let permissions: Permissions = Arc::new(permissions.into_iter().map(|p| p.code).collect());
+                ctx.permissions.put(user_id, permissions.clone());
+                Ok(permissions)
+            }
+        }
+    }
+}
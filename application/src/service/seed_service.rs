@@ -0,0 +1,327 @@
+//! 种子数据：按profile区分的用户、角色及用户角色，用于开发、测试及演示环境
+//!
+//! 数据集`<set>`依次查找外部配置文件所在目录下的`seeds/<set>.yaml`、`seeds/<set>.json`及内置的`seeds/<set>.yaml`，
+//! 内置的数据集包含已知的密码，只在调试构建中可用；
+//! 导入是幂等的：用户、角色按名称更新，用户的角色及角色的权限替换为数据集中的值
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+use config::{Config, File, FileFormat};
+use sea_orm::sqlx::types::chrono::Utc;
+use sea_orm::{IntoActiveModel, Set, TransactionTrait};
+use serde::Deserialize;
+
+use common::domain::user::permission::Model as PermissionModel;
+use common::domain::user::user::{ActiveModel as UserActiveModel, Model as UserModel};
+use common::domain::user::user_token::PURPOSE_REFRESH;
+use common::domain::user::{PermissionRepository, RoleRepository, UserRepository, UserTokenRepository};
+
+#[cfg(debug_assertions)]
+use crate::assets::AssetsSeeds;
+use crate::auth::password::{self, Argon2Config};
+use crate::configs::ConfigSource;
+use crate::core::context::Context;
+use crate::core::errors::{AppError, AppResult};
+use crate::core::secret;
+
+/// 未指定数据集且未激活profile时导入的数据集
+pub const DEFAULT_SEED_SET: &str = "dev";
+/// 角色拥有所有权限
+const ALL_PERMISSIONS: &str = "*";
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Fixture {
+    pub roles: Vec<SeedRole>,
+    pub users: Vec<SeedUser>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SeedRole {
+    pub name: String,
+    /// 权限编码，`*`表示所有权限
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SeedUser {
+    pub username: String,
+    pub email: String,
+    pub phone: String,
+    /// 明文密码，不校验密码策略，为空的用户不能登录
+    #[serde(default)]
+    pub password: Option<String>,
+    /// 是否已验证邮箱，默认已验证
+    #[serde(default = "default_true")]
+    pub email_verified: bool,
+    /// 角色名称，数据集中或数据库中已有的角色
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Fixture {
+    /// 按名称查找数据集，外部文件优先于内置文件
+    pub fn load(source: &ConfigSource, set: &str) -> AppResult<Self> {
+        if let Some(dir) = source.path.parent().map(|dir| dir.join("seeds")) {
+            for extension in ["yaml", "json"] {
+                let path = dir.join(format!("{}.{}", set, extension));
+                if path.is_file() {
+                    return Self::from_file(&path);
+                }
+            }
+        }
+        match embedded(set) {
+            Some(content) => Self::parse(&content, FileFormat::Yaml),
+            None => Err(AppError::Seed(format!("can not find seed set `{}`", set))),
+        }
+    }
+
+    /// 按扩展名识别YAML或JSON
+    pub fn from_file(path: &Path) -> AppResult<Self> {
+        let format = match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => FileFormat::Yaml,
+            Some("json") => FileFormat::Json,
+            _ => return Err(AppError::Seed(format!("{}: expect a .yaml or .json file", path.display()))),
+        };
+        Self::parse(&std::fs::read_to_string(path)?, format)
+    }
+
+    /// 解析后校验，支持`${env:NAME}`、`${file:PATH}`引用密码等
+    pub fn parse(content: &str, format: FileFormat) -> AppResult<Self> {
+        let mut value: config::Value = Config::builder().add_source(File::from_str(content, format)).build()?.try_deserialize()?;
+        secret::resolve(&mut value, "", &|name| std::env::var(name).ok())?;
+        let fixture: Fixture = value.try_deserialize()?;
+        fixture.validate()?;
+        Ok(fixture)
+    }
+
+    fn validate(&self) -> AppResult<()> {
+        let mut roles = HashSet::new();
+        for role in &self.roles {
+            if role.name.trim().is_empty() || !roles.insert(role.name.as_str()) {
+                return Err(AppError::Seed(format!("role name `{}` is blank or duplicated", role.name)));
+            }
+        }
+        let mut users = HashSet::new();
+        for user in &self.users {
+            if user.username.trim().is_empty() || !users.insert(user.username.as_str()) {
+                return Err(AppError::Seed(format!("username `{}` is blank or duplicated", user.username)));
+            }
+            if user.email.trim().is_empty() || user.phone.trim().is_empty() {
+                return Err(AppError::Seed(format!("user `{}`: email and phone are required", user.username)));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(debug_assertions)]
+fn embedded(set: &str) -> Option<String> {
+    AssetsSeeds::get(&format!("{}.yaml", set)).map(|file| String::from_utf8_lossy(&file.data).into_owned())
+}
+
+#[cfg(not(debug_assertions))]
+fn embedded(_set: &str) -> Option<String> {
+    None
+}
+
+/// 导入的数量
+#[derive(Debug, Default, PartialEq)]
+pub struct SeedReport {
+    pub roles_created: usize,
+    pub roles_updated: usize,
+    pub users_created: usize,
+    pub users_updated: usize,
+}
+
+impl fmt::Display for SeedReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "roles: {} created, {} updated; users: {} created, {} updated",
+            self.roles_created, self.roles_updated, self.users_created, self.users_updated
+        )
+    }
+}
+
+pub struct SeedService;
+
+impl SeedService {
+    /// 在一个事务中导入，任一数据有误时不修改数据库
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn load(ctx: &Context, fixture: &Fixture) -> AppResult<SeedReport> {
+        let argon2 = ctx.reloader.current().password.argon2.clone();
+        let mut report = SeedReport::default();
+        let txn = ctx.db.begin().await?;
+
+        let permissions = PermissionRepository::find_all(&txn).await?;
+        let names: Vec<String> = fixture.roles.iter().map(|r| r.name.clone()).collect();
+        let mut roles: HashMap<String, i64> = RoleRepository::find_by_names(&txn, &names).await?.into_iter().map(|r| (r.name, r.id)).collect();
+        for role in &fixture.roles {
+            let role_id = match roles.get(&role.name) {
+                Some(&id) => {
+                    report.roles_updated += 1;
+                    id
+                }
+                None => {
+                    report.roles_created += 1;
+                    let id = RoleRepository::insert(&txn, &role.name).await?.id;
+                    roles.insert(role.name.clone(), id);
+                    id
+                }
+            };
+            let permission_ids = permission_ids(&permissions, role)?;
+            RoleRepository::set_permissions(&txn, role_id, &permission_ids).await?;
+        }
+        // 用户引用的数据库中已有的角色
+        let others: Vec<String> = fixture.users.iter().flat_map(|u| &u.roles).filter(|name| !roles.contains_key(*name)).cloned().collect();
+        if !others.is_empty() {
+            roles.extend(RoleRepository::find_by_names(&txn, &others).await?.into_iter().map(|r| (r.name, r.id)));
+        }
+
+        let usernames: Vec<String> = fixture.users.iter().map(|u| u.username.clone()).collect();
+        let existing: HashMap<String, UserModel> = UserRepository::find_by_usernames(&txn, &usernames).await?.into_iter().map(|u| (u.username.clone(), u)).collect();
+        let now = Utc::now();
+        for user in &fixture.users {
+            let role_ids = user
+                .roles
+                .iter()
+                .map(|name| roles.get(name).copied().ok_or_else(|| AppError::Seed(format!("user `{}`: unknown role `{}`", user.username, name))))
+                .collect::<AppResult<Vec<i64>>>()?;
+            let current = existing.get(&user.username);
+            let hashed = hash_password(user.password.as_deref(), current.map(|u| u.password.as_str()), &argon2).await?;
+            let user_id = match current {
+                Some(model) => {
+                    report.users_updated += 1;
+                    let mut active = model.clone().into_active_model();
+                    active.email = Set(user.email.clone());
+                    active.phone = Set(user.phone.clone());
+                    active.failed_logins = Set(0);
+                    active.locked_until = Set(None);
+                    if let Some(hashed) = hashed {
+                        active.password = Set(hashed);
+                        active.password_changed_at = Set(Some(now));
+                        UserTokenRepository::invalidate(&txn, model.id, PURPOSE_REFRESH, now).await?;
+                    }
+                    match (user.email_verified, model.email_verified_at) {
+                        (true, None) => active.email_verified_at = Set(Some(now)),
+                        (false, Some(_)) => active.email_verified_at = Set(None),
+                        _ => {}
+                    }
+                    UserRepository::update(&txn, active).await?.id
+                }
+                None => {
+                    report.users_created += 1;
+                    let model = UserActiveModel {
+                        username: Set(user.username.clone()),
+                        password: Set(hashed.unwrap_or_default()),
+                        email: Set(user.email.clone()),
+                        phone: Set(user.phone.clone()),
+                        email_verified_at: Set(user.email_verified.then_some(now)),
+                        ..Default::default()
+                    };
+                    UserRepository::insert(&txn, model).await?.id
+                }
+            };
+            UserRepository::set_roles(&txn, user_id, &role_ids).await?;
+        }
+        txn.commit().await?;
+        ctx.permissions.clear();
+        tracing::info!("seed data loaded, {}", report);
+        Ok(report)
+    }
+
+    /// 删除所有表后重新执行迁移再导入，数据库中的其他数据都会丢失
+    pub(crate) async fn reset(ctx: &Context, fixture: &Fixture) -> AppResult<SeedReport> {
+        common::migration::fresh(&ctx.db).await?;
+        Self::load(ctx, fixture).await
+    }
+}
+
+fn permission_ids(permissions: &[PermissionModel], role: &SeedRole) -> AppResult<Vec<i64>> {
+    if role.permissions.iter().any(|code| code == ALL_PERMISSIONS) {
+        return Ok(permissions.iter().map(|p| p.id).collect());
+    }
+    role.permissions
+        .iter()
+        .map(|code| {
+            let permission = permissions.iter().find(|p| &p.code == code);
+            permission.map(|p| p.id).ok_or_else(|| AppError::Seed(format!("role `{}`: unknown permission `{}`", role.name, code)))
+        })
+        .collect()
+}
+
+/// 密码未变化时返回空，避免每次导入都重新哈希；未设置密码时清空已有的密码
+async fn hash_password(password: Option<&str>, current: Option<&str>, argon2: &Argon2Config) -> AppResult<Option<String>> {
+    match (password, current) {
+        (None, Some("")) => Ok(None),
+        (None, _) => Ok(Some(String::new())),
+        (Some(password), Some(current)) if password::verify_blocking(password.to_string(), current.to_string()).await? => Ok(None),
+        (Some(password), _) => Ok(Some(password::hash_blocking(password.to_string(), argon2.clone()).await?)),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::test_support::TestApp;
+
+    /// 数据库重置为内置的数据集
+    pub(crate) async fn reset(ctx: &Context, set: &str) -> SeedReport {
+        let fixture = Fixture::load(&ConfigSource::new("/not/exists/application.yaml", None), set).unwrap();
+        SeedService::reset(ctx, &fixture).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_seed() {
+        let app = TestApp::new().await;
+        let ctx = &app.ctx;
+        let report = reset(ctx, "test").await;
+        // 迁移中已创建admin角色
+        assert_eq!((report.roles_created, report.roles_updated, report.users_created), (1, 1, 2));
+        let admin = UserRepository::find_by_username(&ctx.db, "admin").await.unwrap().unwrap();
+        assert!(password::verify("Test-Passw0rd", &admin.password) && admin.email_verified_at.is_some());
+        let codes: Vec<String> = PermissionRepository::find_by_user(&ctx.db, admin.id).await.unwrap().into_iter().map(|p| p.code).collect();
+        assert_eq!(codes, ["user:read", "user:write"]);
+
+        // 再次导入时按名称更新，恢复被修改的数据
+        let mut active = admin.clone().into_active_model();
+        active.email = Set("changed@example.com".to_string());
+        UserRepository::update(ctx.db.as_ref(), active).await.unwrap();
+        UserRepository::set_roles(ctx.db.as_ref(), admin.id, &[]).await.unwrap();
+        let fixture = Fixture::load(&ConfigSource::new("/not/exists/application.yaml", None), "test").unwrap();
+        let report = SeedService::load(ctx, &fixture).await.unwrap();
+        assert_eq!(report, SeedReport { roles_created: 0, roles_updated: 2, users_created: 0, users_updated: 2 });
+        let reloaded = UserRepository::find_by_username(&ctx.db, "admin").await.unwrap().unwrap();
+        assert_eq!((reloaded.email.as_str(), reloaded.password.as_str()), ("admin@example.com", admin.password.as_str()));
+        assert_eq!(PermissionRepository::find_by_user(&ctx.db, admin.id).await.unwrap().len(), 2);
+
+        // 数据有误时整体回滚
+        let json = r#"{"users": [{"username": "carol", "email": "carol@example.com", "phone": "13800000009", "roles": ["nobody"]}]}"#;
+        let fixture = Fixture::parse(json, FileFormat::Json).unwrap();
+        let error = SeedService::load(ctx, &fixture).await.unwrap_err();
+        assert_eq!(error.to_string(), "invalid seed data: user `carol`: unknown role `nobody`");
+        assert!(UserRepository::find_by_username(&ctx.db, "carol").await.unwrap().is_none());
+        assert!(Fixture::parse(r#"{"roles": [{"name": "a"}, {"name": "a"}]}"#, FileFormat::Json).is_err());
+        assert!(Fixture::load(&ConfigSource::new("/not/exists/application.yaml", None), "prod").is_err());
+
+        // 重置后删除数据集之外的数据
+        let dave = UserActiveModel {
+            username: Set("dave".to_string()),
+            password: Set(String::new()),
+            email: Set("dave@example.com".to_string()),
+            phone: Set("13800000008".to_string()),
+            ..Default::default()
+        };
+        UserRepository::insert(ctx.db.as_ref(), dave).await.unwrap();
+        assert_eq!(reset(ctx, "test").await.users_created, 2);
+        assert!(UserRepository::find_by_username(&ctx.db, "dave").await.unwrap().is_none());
+    }
+}
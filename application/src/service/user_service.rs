@@ -1,53 +1,375 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 
-use salvo::oapi::ToSchema;
-use serde::Serialize;
+use salvo::oapi::{ToParameters, ToSchema};
+use sea_orm::sqlx::types::chrono::Utc;
+use sea_orm::{Order, Set, TransactionTrait};
+use serde::{Deserialize, Serialize};
 
-use common::domain::user::user::Model as User;
-use common::domain::user::UserRepository;
+use common::domain::user::user::{ActiveModel, Column, Model as User};
+use common::domain::user::user_token::PURPOSE_VERIFY_EMAIL;
+use common::domain::user::{RoleRepository, UserFilter, UserRepository, UserTokenRepository};
+use common::queue::message::event::cluster_event_proto::ClusterEvent;
+use common::queue::message::event::{ClusterEventProto, UserEvent, UserEventType};
 
+use crate::auth::password;
 use crate::core::context::Context;
 use crate::core::errors::{AppError, AppResult};
+use crate::core::salvo::api_result::Page;
+
+const DEFAULT_PAGE_SIZE: u64 = 20;
+const MAX_PAGE_SIZE: u64 = 100;
 
 pub struct UserService;
 
 impl UserService {
     #[tracing::instrument(skip(ctx))]
     pub(crate) async fn find_by_id(ctx: &Arc<Context>, user_id: i64) -> AppResult<UserVo> {
-        let user = UserRepository::find_by_id(&ctx.db, user_id).await?;
-        match user {
-            None => Err(AppError::ApiRequestParam(format!("can not find user with id: {}", user_id))),
-            Some(user) => Ok(user.into())
+        let user = Self::get(ctx, user_id).await?;
+        let role_ids = Self::role_ids(ctx, user_id).await?;
+        Ok(UserVo::new(user, role_ids))
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    pub(crate) async fn find_page(ctx: &Arc<Context>, query: UserQuery) -> AppResult<Page<UserVo>> {
+        let page = query.page.unwrap_or(1).max(1);
+        let size = query.size.unwrap_or(DEFAULT_PAGE_SIZE);
+        if size == 0 || size > MAX_PAGE_SIZE {
+            return Err(AppError::BadRequest(format!("size must be 1 to {}", MAX_PAGE_SIZE)));
+        }
+        let order = parse_sort(query.sort.as_deref())?;
+        let filter = UserFilter {
+            username: non_blank(query.username),
+            email: non_blank(query.email),
+            phone: non_blank(query.phone),
+            role_id: query.role_id,
+        };
+        let (users, total) = UserRepository::find_page(&ctx.db, &filter, order, page - 1, size).await?;
+        let ids: Vec<i64> = users.iter().map(|u| u.id).collect();
+        let mut roles = UserRepository::find_role_ids(ctx.db.as_ref(), &ids).await?;
+        let items = users
+            .into_iter()
+            .map(|u| {
+                let role_ids = roles.remove(&u.id).unwrap_or_default();
+                UserVo::new(u, role_ids)
+            })
+            .collect();
+        Ok(Page { items, total, page, size })
+    }
+
+    /// 管理员创建的用户没有密码，需要通过重置密码设置
+    #[tracing::instrument(skip(ctx))]
+    pub(crate) async fn create(ctx: &Arc<Context>, req: SaveUser) -> AppResult<UserVo> {
+        Self::create_with_password(ctx, req, None).await
+    }
+
+    /// 注册时同时设置密码，密码需要满足密码策略
+    #[tracing::instrument(skip(ctx, password))]
+    pub(crate) async fn create_with_password(ctx: &Arc<Context>, req: SaveUser, password: Option<String>) -> AppResult<UserVo> {
+        let req = req.validate()?;
+        Self::check_unique(ctx, &req, None).await?;
+        // 密码为空的用户不能登录
+        let hashed = match password {
+            Some(password) => {
+                let config = ctx.reloader.current().password.clone();
+                config.check(&password, &req.username)?;
+                password::hash_blocking(password, config.argon2).await?
+            }
+            None => String::new(),
+        };
+        let model = ActiveModel {
+            username: Set(req.username),
+            password: Set(hashed),
+            email: Set(req.email),
+            phone: Set(req.phone),
+            ..Default::default()
+        };
+        let user = UserRepository::insert(ctx.db.as_ref(), model).await?;
+        let vo = UserVo::new(user, Vec::new());
+        publish(ctx, UserEventType::Created, &vo);
+        Ok(vo)
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    pub(crate) async fn update(ctx: &Arc<Context>, user_id: i64, req: SaveUser) -> AppResult<UserVo> {
+        let req = req.validate()?;
+        let user = Self::get(ctx, user_id).await?;
+        Self::check_unique(ctx, &req, Some(user_id)).await?;
+        let email_changed = user.email != req.email;
+        let mut model: ActiveModel = user.into();
+        model.username = Set(req.username);
+        model.email = Set(req.email);
+        model.phone = Set(req.phone);
+        let txn = ctx.db.begin().await?;
+        // 修改邮箱后需要重新验证
+        if email_changed {
+            model.email_verified_at = Set(None);
+            UserTokenRepository::invalidate(&txn, user_id, PURPOSE_VERIFY_EMAIL, Utc::now()).await?;
         }
+        let user = UserRepository::update(&txn, model).await?;
+        txn.commit().await?;
+        let role_ids = Self::role_ids(ctx, user_id).await?;
+        let vo = UserVo::new(user, role_ids);
+        publish(ctx, UserEventType::Updated, &vo);
+        Ok(vo)
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    pub(crate) async fn delete(ctx: &Arc<Context>, user_id: i64) -> AppResult<UserVo> {
+        let user = Self::get(ctx, user_id).await?;
+        let role_ids = Self::role_ids(ctx, user_id).await?;
+        let txn = ctx.db.begin().await?;
+        UserRepository::delete(&txn, user_id).await?;
+        txn.commit().await?;
+        ctx.permissions.evict(user_id);
+        let vo = UserVo::new(user, role_ids);
+        publish(ctx, UserEventType::Deleted, &vo);
+        Ok(vo)
     }
 
+    /// 替换用户的所有角色
     #[tracing::instrument(skip(ctx))]
-    pub(crate) async fn find_by_id_spawn(ctx: &Arc<Context>, user_id: i64) -> AppResult<UserVo> {
-        let span = tracing::info_span!("info-span");
-        let _enter = span.enter();
-        let user = UserRepository::find_by_id(&ctx.db, user_id).await?;
-        match user {
-            None => Err(AppError::ApiRequestParam(format!("can not find user with id: {}", user_id))),
-            Some(user) => Ok(user.into())
+    pub(crate) async fn set_roles(ctx: &Arc<Context>, user_id: i64, role_ids: Vec<i64>) -> AppResult<UserVo> {
+        let user = Self::get(ctx, user_id).await?;
+        let mut role_ids: Vec<i64> = role_ids.into_iter().collect::<HashSet<_>>().into_iter().collect();
+        role_ids.sort_unstable();
+        let exists: HashSet<i64> = RoleRepository::find_by_ids(&ctx.db, &role_ids).await?.into_iter().map(|r| r.id).collect();
+        if let Some(missing) = role_ids.iter().find(|id| !exists.contains(id)) {
+            return Err(AppError::BadRequest(format!("can not find role with id: {}", missing)));
         }
+        let txn = ctx.db.begin().await?;
+        UserRepository::set_roles(&txn, user_id, &role_ids).await?;
+        txn.commit().await?;
+        ctx.permissions.evict(user_id);
+        let vo = UserVo::new(user, role_ids);
+        publish(ctx, UserEventType::Updated, &vo);
+        Ok(vo)
     }
+
+    async fn get(ctx: &Arc<Context>, user_id: i64) -> AppResult<User> {
+        UserRepository::find_by_id(&ctx.db, user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("can not find user with id: {}", user_id)))
+    }
+
+    async fn role_ids(ctx: &Arc<Context>, user_id: i64) -> AppResult<Vec<i64>> {
+        let mut roles = UserRepository::find_role_ids(ctx.db.as_ref(), &[user_id]).await?;
+        Ok(roles.remove(&user_id).unwrap_or_default())
+    }
+
+    /// 用户名、邮箱、手机号不能与其他用户相同
+    async fn check_unique(ctx: &Arc<Context>, req: &SaveUser, exclude_id: Option<i64>) -> AppResult<()> {
+        let Some(other) = UserRepository::find_conflict(&ctx.db, &req.username, &req.email, &req.phone, exclude_id).await? else {
+            return Ok(());
+        };
+        let field = if other.username == req.username {
+            "username"
+        } else if other.email == req.email {
+            "email"
+        } else {
+            "phone"
+        };
+        Err(AppError::Conflict(format!("{} already exists", field)))
+    }
+}
+
+/// 发布用户变更事件，事件总线已关闭时只记录日志
+fn publish(ctx: &Context, event_type: UserEventType, user: &UserVo) {
+    let event = UserEvent {
+        event_type: event_type.into(),
+        id: user.id,
+        username: user.username.clone(),
+        email: user.email.clone(),
+        phone: user.phone.clone(),
+        role_ids: user.role_ids.clone(),
+    };
+    let event = ClusterEventProto {
+        ts: Utc::now().timestamp_millis(),
+        cluster_event: Some(ClusterEvent::UserEvent(event)),
+    };
+    if let Err(e) = ctx.cluster_event.send(event) {
+        tracing::warn!("publish user event of {} fail: {}", user.id, e);
+    }
+}
+
+/// `sort`：字段名，以`-`开头时倒序，eg:`-username`
+fn parse_sort(sort: Option<&str>) -> AppResult<(Column, Order)> {
+    let Some(sort) = sort.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Ok((Column::Id, Order::Asc));
+    };
+    let (field, order) = match sort.strip_prefix('-') {
+        Some(field) => (field, Order::Desc),
+        None => (sort, Order::Asc),
+    };
+    let column = match field {
+        "id" => Column::Id,
+        "username" => Column::Username,
+        "email" => Column::Email,
+        "phone" => Column::Phone,
+        _ => return Err(AppError::BadRequest(format!("can not sort by `{}`", field))),
+    };
+    Ok((column, order))
+}
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// 用户列表查询条件
+#[derive(Debug, Deserialize, ToParameters)]
+#[salvo(parameters(default_parameter_in = Query))]
+pub struct UserQuery {
+    /// 页码，从1开始，默认1
+    pub page: Option<u64>,
+    /// 每页数量，默认20，最大100
+    pub size: Option<u64>,
+    /// 用户名包含
+    pub username: Option<String>,
+    /// 邮箱包含
+    pub email: Option<String>,
+    /// 手机号包含
+    pub phone: Option<String>,
+    /// 拥有该角色
+    pub role_id: Option<i64>,
+    /// 排序字段：`id`/`username`/`email`/`phone`，以`-`开头时倒序，默认`id`
+    pub sort: Option<String>,
 }
 
-#[derive(Serialize, ToSchema)]
+/// 新增或修改用户
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SaveUser {
+    /// 用户名，3~64位字母、数字及`_.-`
+    pub username: String,
+    pub email: String,
+    /// 手机号，5~20位数字，可以`+`开头
+    pub phone: String,
+}
+
+impl SaveUser {
+    /// 去掉首尾空白后校验
+    fn validate(self) -> AppResult<Self> {
+        let username = self.username.trim().to_string();
+        let email = self.email.trim().to_string();
+        let phone = self.phone.trim().to_string();
+        if !(3..=64).contains(&username.len()) || !username.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-')) {
+            return Err(AppError::BadRequest("username must be 3 to 64 letters, digits or `_.-`".to_string()));
+        }
+        let valid_email = email.len() <= 255
+            && email.split_once('@').is_some_and(|(name, domain)| !name.is_empty() && domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.'))
+            && !email.contains(char::is_whitespace);
+        if !valid_email {
+            return Err(AppError::BadRequest("email is invalid".to_string()));
+        }
+        let digits = phone.strip_prefix('+').unwrap_or(&phone);
+        if !(5..=20).contains(&phone.len()) || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(AppError::BadRequest("phone must be 5 to 20 digits".to_string()));
+        }
+        Ok(Self { username, email, phone })
+    }
+}
+
+/// 修改用户的角色
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetRoles {
+    pub role_ids: Vec<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct UserVo {
     pub id: i64,
     pub username: String,
     pub email: String,
     pub phone: String,
+    pub role_ids: Vec<i64>,
 }
 
-impl From<User> for UserVo {
-    fn from(value: User) -> Self {
+impl UserVo {
+    fn new(value: User, role_ids: Vec<i64>) -> Self {
         Self {
             id: value.id,
             username: value.username,
             email: value.email,
             phone: value.phone,
+            role_ids,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use salvo::http::StatusCode;
+
+    use super::*;
+    use crate::test_support::TestApp;
+
+    fn save(username: &str, email: &str, phone: &str) -> SaveUser {
+        SaveUser { username: username.to_string(), email: email.to_string(), phone: phone.to_string() }
+    }
+
+    fn query(sort: &str) -> UserQuery {
+        UserQuery { page: None, size: None, username: None, email: None, phone: None, role_id: None, sort: Some(sort.to_string()) }
+    }
+
+    #[tokio::test]
+    async fn test_user_crud() {
+        let app = TestApp::new().await;
+        let ctx = &app.ctx;
+        // 迁移中创建的角色
+        let admin = RoleRepository::find_by_names(ctx.db.as_ref(), &["admin".to_string()]).await.unwrap()[0].id;
+        let mut events = ctx.cluster_event.subscribe().unwrap();
+
+        let alice = UserService::create(ctx, save(" alice ", "alice@example.com", "+8613800000001")).await.unwrap();
+        assert_eq!(alice.username, "alice");
+        let bob = UserService::create(ctx, save("bob", "bob@example.com", "13800000002")).await.unwrap();
+        for (req, status, message) in [
+            (save("alice", "a2@example.com", "13800000003"), StatusCode::CONFLICT, "username already exists"),
+            (save("alice2", "bob@example.com", "13800000003"), StatusCode::CONFLICT, "email already exists"),
+            (save("alice2", "a2@example.com", "13800000002"), StatusCode::CONFLICT, "phone already exists"),
+            (save("a", "a2@example.com", "13800000003"), StatusCode::BAD_REQUEST, "username must be 3 to 64 letters, digits or `_.-`"),
+            (save("alice2", "example.com", "13800000003"), StatusCode::BAD_REQUEST, "email is invalid"),
+            (save("alice2", "a2@example.com", "138-0000"), StatusCode::BAD_REQUEST, "phone must be 5 to 20 digits"),
+        ] {
+            let e = UserService::create(ctx, req).await.unwrap_err();
+            assert_eq!((e.status_code(), e.to_string().as_str()), (status, message));
         }
+
+        // 修改时可以保留自己的用户名
+        let bob = UserService::update(ctx, bob.id, save("bob", "bob@example.org", "13800000002")).await.unwrap();
+        assert_eq!(bob.email, "bob@example.org");
+        let bob = UserService::set_roles(ctx, bob.id, vec![admin, admin]).await.unwrap();
+        assert_eq!(bob.role_ids, vec![admin]);
+        assert_eq!(UserService::set_roles(ctx, bob.id, vec![0]).await.unwrap_err().to_string(), "can not find role with id: 0");
+
+        let page = UserService::find_page(ctx, query("-username")).await.unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.items.iter().map(|u| u.username.as_str()).collect::<Vec<_>>(), ["bob", "alice"]);
+        assert_eq!(page.items[0].role_ids, vec![admin]);
+        let page = UserService::find_page(ctx, UserQuery { role_id: Some(admin), ..query("id") }).await.unwrap();
+        assert_eq!(page.items.iter().map(|u| u.id).collect::<Vec<_>>(), [bob.id]);
+        let page = UserService::find_page(ctx, UserQuery { username: Some("li".to_string()), size: Some(1), ..query("id") }).await.unwrap();
+        assert_eq!((page.total, page.items[0].id), (1, alice.id));
+        assert!(UserService::find_page(ctx, query("password")).await.is_err());
+
+        UserService::delete(ctx, bob.id).await.unwrap();
+        assert!(UserService::find_by_id(ctx, bob.id).await.is_err_and(|e| e.status_code() == StatusCode::NOT_FOUND));
+        assert!(UserRepository::find_role_ids(ctx.db.as_ref(), &[bob.id]).await.unwrap().is_empty());
+
+        let mut received = Vec::new();
+        for _ in 0..5 {
+            let Some(ClusterEvent::UserEvent(event)) = events.recv_mut().await.unwrap().unwrap().cluster_event else {
+                panic!("not a user event");
+            };
+            received.push((event.event_type(), event.id, event.email, event.role_ids));
+        }
+        assert_eq!(
+            received,
+            [
+                (UserEventType::Created, alice.id, "alice@example.com".to_string(), vec![]),
+                (UserEventType::Created, bob.id, "bob@example.com".to_string(), vec![]),
+                (UserEventType::Updated, bob.id, "bob@example.org".to_string(), vec![]),
+                (UserEventType::Updated, bob.id, "bob@example.org".to_string(), vec![admin]),
+                (UserEventType::Deleted, bob.id, "bob@example.org".to_string(), vec![admin]),
+            ]
+        );
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,130 @@
+//! OpenTelemetry分布式追踪：以OTLP(http/protobuf)导出span，通过W3C `traceparent`请求头传递追踪上下文
+use std::str::FromStr;
+use std::time::Duration;
+
+use opentelemetry::propagation::{Extractor, Injector, TextMapPropagator};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use salvo::http::header::{HeaderName, HeaderValue};
+use salvo::http::HeaderMap;
+use serde::Deserialize;
+
+use crate::configs::{parse_http_uri, ConfigErrors};
+use crate::core::errors::{AppError, AppResult};
+
+/// OTLP导出配置，span是否导出同样受日志等级控制
+#[serde_with::serde_as]
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Otlp {
+    /// 是否导出span，默认`false`
+    #[serde(default)]
+    pub enabled: bool,
+    /// 采集器地址，默认`http://127.0.0.1:4318/v1/traces`
+    #[serde(default = "default_endpoint")]
+    pub endpoint: String,
+    /// 服务名，默认`application`
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+    /// 采样比例`0.0`~`1.0`，上游已决定是否采样时以上游为准，默认`1.0`
+    #[serde(default = "default_sample_ratio")]
+    pub sample_ratio: f64,
+    /// 导出超时时间（秒），默认`10`
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_timeout")]
+    pub timeout: Duration,
+}
+
+impl Default for Otlp {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: default_endpoint(),
+            service_name: default_service_name(),
+            sample_ratio: default_sample_ratio(),
+            timeout: default_timeout(),
+        }
+    }
+}
+
+fn default_endpoint() -> String {
+    "http://127.0.0.1:4318/v1/traces".to_string()
+}
+
+fn default_service_name() -> String {
+    env!("CARGO_PKG_NAME").to_string()
+}
+
+fn default_sample_ratio() -> f64 {
+    1.0
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+impl Otlp {
+    pub(crate) fn validate(&self, errors: &mut ConfigErrors) {
+        if !self.enabled {
+            return;
+        }
+        if let Err(e) = parse_http_uri(&self.endpoint) {
+            errors.push("logging.otlp.endpoint", e);
+        }
+        if self.service_name.trim().is_empty() {
+            errors.push("logging.otlp.service_name", "must not be empty");
+        }
+        if !(0.0..=1.0).contains(&self.sample_ratio) {
+            errors.push("logging.otlp.sample_ratio", format!("{} is not between 0.0 and 1.0", self.sample_ratio));
+        }
+    }
+}
+
+/// 创建批量导出span的TracerProvider，程序退出前需要`shutdown`以导出剩余的span
+pub(crate) fn tracer_provider(config: &Otlp) -> AppResult<SdkTracerProvider> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(config.endpoint.as_str())
+        .with_timeout(config.timeout)
+        .build()
+        .map_err(|e| AppError::Logging(format!("can not create otlp exporter: {}", e)))?;
+    let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sample_ratio)));
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(sampler)
+        .with_resource(Resource::builder().with_service_name(config.service_name.clone()).build())
+        .build())
+}
+
+/// 从请求头的`traceparent`中解析上游的追踪上下文
+pub fn extract(headers: &HeaderMap) -> opentelemetry::Context {
+    TraceContextPropagator::new().extract(&HeaderExtractor(headers))
+}
+
+/// 将追踪上下文写入`traceparent`响应头
+pub fn inject(cx: &opentelemetry::Context, headers: &mut HeaderMap) {
+    TraceContextPropagator::new().inject_context(cx, &mut HeaderInjector(headers))
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_str(key), HeaderValue::from_str(&value)) {
+            self.0.insert(name, value);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! 测试支持，不依赖外部的数据库、邮件服务器：
+//! - [`TestApp`]：内存SQLite上执行了所有迁移的`Context`，及与生产环境相同中间件的`Service`
+//! - [`assert_ok`]、[`assert_err`]：校验`ResponseResult`格式的响应
+//! - [`LogCapture`]、[`EmailCapture`]：捕获日志及发送的邮件
+
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use salvo::http::StatusCode;
+use salvo::test::ResponseExt;
+use salvo::{Response, Service};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tracing::subscriber::DefaultGuard;
+use tracing_subscriber::layer::{Identity, SubscriberExt};
+use tracing_subscriber::{Layer, Registry};
+
+use common::email::{EmailError, EmailService, EmailTransport, MemoryTransport, Message, SentEmail};
+
+use crate::auth::jwt::tests::{claims, hs256, sign};
+use crate::auth::password::tests::weak;
+use crate::configs::{AppConfig, ConfigSource};
+use crate::core::context::Context;
+use crate::service::email_outbox_service::EmailOutboxService;
+use crate::service::seed_service::{Fixture, SeedService};
+
+/// 请求地址的前缀，eg:`format!("{}/user/ins/1", BASE_URL)`
+pub(crate) const BASE_URL: &str = "http://127.0.0.1:5801";
+
+pub(crate) struct TestApp {
+    pub ctx: Arc<Context>,
+    pub emails: EmailCapture,
+}
+
+impl TestApp {
+    pub(crate) async fn new() -> Self {
+        Self::with_config(|_| {}).await
+    }
+
+    /// 默认配置了HS256的jwt、较小的Argon2参数，可在`f`中修改
+    pub(crate) async fn with_config(f: impl FnOnce(&mut AppConfig)) -> Self {
+        let mut config = crate::configs::tests::load("/not/exists/application.yaml", None, &[("APP__DATA_SOURCE__URL", "sqlite::memory:")]);
+        config.jwt = Some(hs256());
+        config.password.argon2 = weak();
+        f(&mut config);
+        let mut ctx = Context::new(config).await.unwrap();
+        let emails = EmailCapture::install(&mut ctx);
+        common::migration::up_to(&ctx.db, None).await.unwrap();
+        Self { ctx: Arc::new(ctx), emails }
+    }
+
+    /// 导入内置的数据集，eg:`test`
+    pub(crate) async fn seed(self, set: &str) -> Self {
+        let fixture = Fixture::load(&ConfigSource::new("/not/exists/application.yaml", None), set).unwrap();
+        SeedService::load(&self.ctx, &fixture).await.unwrap();
+        self
+    }
+
+    pub(crate) fn service(&self) -> Service {
+        crate::controller::service(self.ctx.clone())
+    }
+
+    /// 用户的访问令牌，权限取决于用户的角色
+    pub(crate) fn token(&self, user_id: i64) -> String {
+        sign(&claims(&user_id.to_string(), 60))
+    }
+
+    /// 发送发件箱中到期的邮件后，返回所有已发送的邮件
+    pub(crate) async fn sent_emails(&self) -> Vec<SentEmail> {
+        EmailOutboxService::deliver_due(&self.ctx).await.unwrap();
+        self.emails.emails()
+    }
+}
+
+/// 成功的响应，返回`data`
+pub(crate) async fn assert_ok<T: DeserializeOwned>(res: &mut Response) -> T {
+    let body = take_json(res).await;
+    assert_eq!(res.status_code.unwrap_or(StatusCode::OK), StatusCode::OK, "{}", body);
+    assert_eq!(body["code"], "200", "{}", body);
+    serde_json::from_value(body["data"].clone()).unwrap_or_else(|e| panic!("can not deserialize data: {}, {}", e, body))
+}
+
+/// 失败的响应：400、401、403、404、409返回对应的状态码，其他错误的状态码为200，错误码为500
+pub(crate) async fn assert_err(res: &mut Response, code: StatusCode, message: &str) {
+    let body = take_json(res).await;
+    let status = if code == StatusCode::INTERNAL_SERVER_ERROR { StatusCode::OK } else { code };
+    assert_eq!(res.status_code.unwrap_or(StatusCode::OK), status, "{}", body);
+    assert_eq!((body["code"].as_str(), body["message"].as_str()), (Some(code.as_str()), Some(message)), "{}", body);
+    assert!(body["traceId"].is_string() && body["data"].is_null(), "{}", body);
+}
+
+async fn take_json(res: &mut Response) -> Value {
+    let body = res.take_string().await.unwrap();
+    serde_json::from_str(&body).unwrap_or_else(|e| panic!("response is not json: {}, {}", e, body))
+}
+
+/// 捕获当前线程的日志，`drop`后恢复；多线程运行时中其他线程的日志不会被捕获
+pub(crate) struct LogCapture {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    _guard: DefaultGuard,
+}
+
+impl LogCapture {
+    pub(crate) fn start() -> Self {
+        Self::with_layer(Identity::new())
+    }
+
+    /// 在捕获日志的同时使用`layer`，eg:OpenTelemetry
+    pub(crate) fn with_layer(layer: impl Layer<Registry> + Send + Sync) -> Self {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry()
+            .with(layer)
+            .with(tracing_subscriber::fmt::layer().with_ansi(false).with_writer(move || BufferWriter(writer.clone())));
+        Self { buffer, _guard: tracing::subscriber::set_default(subscriber) }
+    }
+
+    pub(crate) fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.lock().unwrap_or_else(|e| e.into_inner())).into_owned()
+    }
+
+    pub(crate) fn contains(&self, text: &str) -> bool {
+        self.contents().contains(text)
+    }
+}
+
+struct BufferWriter(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for BufferWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 邮件保存在内存中，可以模拟发送失败
+#[derive(Clone, Default)]
+pub(crate) struct EmailCapture {
+    transport: Arc<CaptureTransport>,
+}
+
+impl EmailCapture {
+    /// 替换`Context`的邮件发送方式
+    pub(crate) fn install(ctx: &mut Context) -> Self {
+        let capture = Self::default();
+        ctx.email = Arc::new(EmailService::with_transport(capture.transport.clone(), "noreply@example.com", None).unwrap());
+        capture
+    }
+
+    /// 已发送的邮件，不包括发件箱中未发送的邮件
+    pub(crate) fn emails(&self) -> Vec<SentEmail> {
+        self.transport.inner.emails()
+    }
+
+    /// 接下来的`n`次发送失败
+    pub(crate) fn fail_next(&self, n: usize) {
+        self.transport.failures.store(n, Ordering::SeqCst);
+    }
+}
+
+#[derive(Default)]
+struct CaptureTransport {
+    failures: AtomicUsize,
+    inner: MemoryTransport,
+}
+
+impl CaptureTransport {
+    fn check(&self) -> Result<(), EmailError> {
+        match self.failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)) {
+            Ok(_) => Err(EmailError::Transport("connection refused".to_string())),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailTransport for CaptureTransport {
+    async fn send(&self, message: Message) -> Result<(), EmailError> {
+        self.check()?;
+        self.inner.send(message).await
+    }
+
+    async fn send_email(&self, message: Message, email: SentEmail) -> Result<(), EmailError> {
+        self.check()?;
+        self.inner.send_email(message, email).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use salvo::test::TestClient;
+
+    use common::domain::user::UserRepository;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_app() {
+        let app = TestApp::new().await.seed("test").await;
+        let service = app.service();
+        let admin = UserRepository::find_by_username(&app.ctx.db, "admin").await.unwrap().unwrap();
+        let viewer = UserRepository::find_by_username(&app.ctx.db, "viewer").await.unwrap().unwrap();
+        let url = format!("{}/user/ins/{}", BASE_URL, admin.id);
+
+        let logs = LogCapture::start();
+        let mut res = TestClient::get(&url).bearer_auth(app.token(viewer.id)).send(&service).await;
+        let user: Value = assert_ok(&mut res).await;
+        assert_eq!((user["id"].as_i64(), user["username"].as_str()), (Some(admin.id), Some("admin")));
+        assert!(logs.contains(&format!("/user/ins/{}", admin.id)));
+        let mut res = TestClient::get(format!("{}/user/spawn/{}", BASE_URL, admin.id)).bearer_auth(app.token(viewer.id)).send(&service).await;
+        let user: Value = assert_ok(&mut res).await;
+        assert_eq!(user["id"].as_i64(), Some(admin.id));
+
+        let mut res = TestClient::get(&url).send(&service).await;
+        assert_err(&mut res, StatusCode::UNAUTHORIZED, "missing bearer token").await;
+        let body = serde_json::json!({"username": "admin", "email": "admin@example.com", "phone": "13800000000"});
+        let mut res = TestClient::put(&url).bearer_auth(app.token(viewer.id)).json(&body).send(&service).await;
+        assert_err(&mut res, StatusCode::FORBIDDEN, "missing permission: user:write").await;
+        let mut res = TestClient::get(format!("{}/user/ins/0", BASE_URL)).bearer_auth(app.token(admin.id)).send(&service).await;
+        assert_err(&mut res, StatusCode::NOT_FOUND, "can not find user with id: 0").await;
+        drop(logs);
+
+        assert!(app.sent_emails().await.is_empty());
+    }
+}
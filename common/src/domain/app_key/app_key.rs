@@ -0,0 +1,27 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::{DeriveEntityModel, EnumIter};
+
+/// 外部应用的访问密钥，只保存密钥的摘要
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "app_key")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub app_name: String,
+    /// 密钥的前几位，用于识别密钥
+    pub key_prefix: String,
+    /// 密钥的SHA-256摘要（hex）
+    #[sea_orm(unique)]
+    pub key_hash: String,
+    /// 以空格分隔的权限范围
+    pub scopes: String,
+    pub expires_at: Option<DateTimeUtc>,
+    pub revoked_at: Option<DateTimeUtc>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,36 @@
+use sea_orm::prelude::DateTimeUtc;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DbConn, DbErr, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set};
+
+use crate::domain::app_key::app_key::{ActiveModel, Column, Entity as AppKeyEntity, Model as AppKeyModel};
+
+#[allow(clippy::module_inception)]
+pub mod app_key;
+
+pub struct AppKeyRepository;
+
+impl AppKeyRepository {
+    pub async fn find_by_hash(db: &DbConn, key_hash: &str) -> Result<Option<AppKeyModel>, DbErr> {
+        AppKeyEntity::find().filter(Column::KeyHash.eq(key_hash)).one(db).await
+    }
+
+    pub async fn find_all(db: &DbConn) -> Result<Vec<AppKeyModel>, DbErr> {
+        AppKeyEntity::find().order_by_asc(Column::Id).all(db).await
+    }
+
+    pub async fn insert(db: &DbConn, model: ActiveModel) -> Result<AppKeyModel, DbErr> {
+        model.insert(db).await
+    }
+
+    /// 吊销密钥，已吊销的保持原吊销时间，密钥不存在时返回`None`
+    pub async fn revoke(db: &DbConn, id: i64, now: DateTimeUtc) -> Result<Option<AppKeyModel>, DbErr> {
+        let Some(model) = AppKeyEntity::find_by_id(id).one(db).await? else {
+            return Ok(None);
+        };
+        if model.revoked_at.is_some() {
+            return Ok(Some(model));
+        }
+        let mut model = model.into_active_model();
+        model.revoked_at = Set(Some(now));
+        model.update(db).await.map(Some)
+    }
+}
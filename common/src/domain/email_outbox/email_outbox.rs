@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::{DeriveEntityModel, EnumIter};
+
+/// 等待发送或重试
+pub const STATUS_PENDING: &str = "pending";
+/// 超过最大重试次数，需要人工处理
+pub const STATUS_DEAD: &str = "dead";
+
+/// 待发送的邮件，发送成功后删除
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "email_outbox")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub recipient: String,
+    pub subject: String,
+    #[sea_orm(column_type = "Text")]
+    pub text_body: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub html_body: Option<String>,
+    /// 正文包含一次性令牌等，`dead`后清空正文，不能重新发送
+    #[sea_orm(default_value = false)]
+    pub sensitive: bool,
+    pub status: String,
+    /// 已失败的次数
+    #[sea_orm(default_value = 0)]
+    pub attempts: i32,
+    /// 下次发送的时间，发送中时为租约的到期时间
+    pub next_attempt_at: DateTimeUtc,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_error: Option<String>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {}
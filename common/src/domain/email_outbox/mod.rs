@@ -0,0 +1,93 @@
+use sea_orm::prelude::DateTimeUtc;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DbConn, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+
+use crate::domain::email_outbox::email_outbox::{ActiveModel, Column, Entity as OutboxEntity, Model as OutboxModel, STATUS_DEAD, STATUS_PENDING};
+
+#[allow(clippy::module_inception)]
+pub mod email_outbox;
+
+pub struct EmailOutboxRepository;
+
+impl EmailOutboxRepository {
+    pub async fn insert(db: &DbConn, model: ActiveModel) -> Result<OutboxModel, DbErr> {
+        model.insert(db).await
+    }
+
+    pub async fn find_by_id(db: &DbConn, id: i64) -> Result<Option<OutboxModel>, DbErr> {
+        OutboxEntity::find_by_id(id).one(db).await
+    }
+
+    /// 到期待发送的邮件，先到期的在前
+    pub async fn find_due(db: &DbConn, now: DateTimeUtc, limit: u64) -> Result<Vec<OutboxModel>, DbErr> {
+        OutboxEntity::find()
+            .filter(Column::Status.eq(STATUS_PENDING))
+            .filter(Column::NextAttemptAt.lte(now))
+            .order_by_asc(Column::NextAttemptAt)
+            .order_by_asc(Column::Id)
+            .limit(limit)
+            .all(db)
+            .await
+    }
+
+    /// 多次失败的邮件，最近创建的在前
+    pub async fn find_dead(db: &DbConn, limit: u64) -> Result<Vec<OutboxModel>, DbErr> {
+        OutboxEntity::find().filter(Column::Status.eq(STATUS_DEAD)).order_by_desc(Column::Id).limit(limit).all(db).await
+    }
+
+    /// 认领到期的邮件，下次发送时间推迟到`lease_until`，其他实例不会重复发送；已被认领时返回`false`
+    pub async fn claim(db: &DbConn, id: i64, now: DateTimeUtc, lease_until: DateTimeUtc) -> Result<bool, DbErr> {
+        let result = OutboxEntity::update_many()
+            .col_expr(Column::NextAttemptAt, Expr::value(lease_until))
+            .filter(Column::Id.eq(id))
+            .filter(Column::Status.eq(STATUS_PENDING))
+            .filter(Column::NextAttemptAt.lte(now))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    /// 发送成功后删除
+    pub async fn delete(db: &DbConn, id: i64) -> Result<(), DbErr> {
+        OutboxEntity::delete_by_id(id).exec(db).await?;
+        Ok(())
+    }
+
+    /// 记录失败，`dead`为`true`时不再重试
+    pub async fn fail(db: &DbConn, id: i64, attempts: i32, next_attempt_at: DateTimeUtc, error: &str, dead: bool) -> Result<(), DbErr> {
+        OutboxEntity::update_many()
+            .col_expr(Column::Attempts, Expr::value(attempts))
+            .col_expr(Column::NextAttemptAt, Expr::value(next_attempt_at))
+            .col_expr(Column::LastError, Expr::value(error))
+            .col_expr(Column::Status, Expr::value(if dead { STATUS_DEAD } else { STATUS_PENDING }))
+            .filter(Column::Id.eq(id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// 清空正文，不再保存其中的令牌
+    pub async fn discard_body(db: &DbConn, id: i64) -> Result<(), DbErr> {
+        OutboxEntity::update_many()
+            .col_expr(Column::TextBody, Expr::value(""))
+            .col_expr(Column::HtmlBody, Expr::value(Option::<String>::None))
+            .filter(Column::Id.eq(id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// 重新发送`dead`的邮件，重试次数清零；不是`dead`或已清空正文时返回`false`
+    pub async fn retry(db: &DbConn, id: i64, now: DateTimeUtc) -> Result<bool, DbErr> {
+        let result = OutboxEntity::update_many()
+            .col_expr(Column::Status, Expr::value(STATUS_PENDING))
+            .col_expr(Column::Attempts, Expr::value(0))
+            .col_expr(Column::NextAttemptAt, Expr::value(now))
+            .filter(Column::Id.eq(id))
+            .filter(Column::Status.eq(STATUS_DEAD))
+            .filter(Column::Sensitive.eq(false))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+}
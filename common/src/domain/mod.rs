@@ -1 +1,3 @@
+pub mod app_key;
+pub mod email_outbox;
 pub mod user;
\ No newline at end of file
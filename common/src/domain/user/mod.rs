@@ -1,10 +1,35 @@
-use sea_orm::{DbConn, DbErr, EntityTrait};
+use std::collections::HashMap;
 
-use crate::domain::user::user::{Entity as UserEntity, Model as UserModel};
+use sea_orm::prelude::DateTimeUtc;
+use sea_orm::sea_query::{Expr, Query};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DbConn, DbErr, EntityTrait, Order, PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
 
+use crate::domain::user::permission::{Entity as PermissionEntity, Model as PermissionModel};
+use crate::domain::user::role::{Entity as RoleEntity, Model as RoleModel};
+use crate::domain::user::user::{ActiveModel as UserActiveModel, Column as UserColumn, Entity as UserEntity, Model as UserModel};
+
+#[allow(clippy::module_inception)]
 pub mod user;
+pub mod permission;
 pub mod role;
+pub mod role_permission;
 pub mod user_role;
+pub mod user_token;
+
+/// 用户列表的查询条件，为空的条件不生效
+#[derive(Clone, Debug, Default)]
+pub struct UserFilter {
+    /// 用户名包含
+    pub username: Option<String>,
+    /// 邮箱包含
+    pub email: Option<String>,
+    /// 手机号包含
+    pub phone: Option<String>,
+    /// 拥有该角色
+    pub role_id: Option<i64>,
+}
 
 pub struct UserRepository;
 
@@ -12,4 +37,233 @@ impl UserRepository {
     pub async fn find_by_id(db: &DbConn, id: i64) -> Result<Option<UserModel>, DbErr> {
         UserEntity::find_by_id(id).one(db).await
     }
-}
\ No newline at end of file
+
+    pub async fn find_by_username(db: &DbConn, username: &str) -> Result<Option<UserModel>, DbErr> {
+        UserEntity::find().filter(UserColumn::Username.eq(username)).one(db).await
+    }
+
+    /// 按用户名批量查询，用于导入种子数据
+    pub async fn find_by_usernames<C: ConnectionTrait>(db: &C, usernames: &[String]) -> Result<Vec<UserModel>, DbErr> {
+        UserEntity::find().filter(UserColumn::Username.is_in(usernames.iter().cloned())).all(db).await
+    }
+
+    pub async fn find_by_email(db: &DbConn, email: &str) -> Result<Option<UserModel>, DbErr> {
+        UserEntity::find().filter(UserColumn::Email.eq(email)).one(db).await
+    }
+
+    /// 用户名、邮箱或手机号与其他用户相同的用户
+    pub async fn find_conflict(db: &DbConn, username: &str, email: &str, phone: &str, exclude_id: Option<i64>) -> Result<Option<UserModel>, DbErr> {
+        let mut condition = Condition::all().add(
+            Condition::any()
+                .add(UserColumn::Username.eq(username))
+                .add(UserColumn::Email.eq(email))
+                .add(UserColumn::Phone.eq(phone)),
+        );
+        if let Some(id) = exclude_id {
+            condition = condition.add(UserColumn::Id.ne(id));
+        }
+        UserEntity::find().filter(condition).one(db).await
+    }
+
+    /// 分页查询，`page`从0开始，返回当前页及总数
+    pub async fn find_page(db: &DbConn, filter: &UserFilter, order: (UserColumn, Order), page: u64, size: u64) -> Result<(Vec<UserModel>, u64), DbErr> {
+        let mut condition = Condition::all();
+        if let Some(username) = &filter.username {
+            condition = condition.add(UserColumn::Username.contains(username));
+        }
+        if let Some(email) = &filter.email {
+            condition = condition.add(UserColumn::Email.contains(email));
+        }
+        if let Some(phone) = &filter.phone {
+            condition = condition.add(UserColumn::Phone.contains(phone));
+        }
+        if let Some(role_id) = filter.role_id {
+            let users = Query::select()
+                .column(user_role::Column::UserId)
+                .from(user_role::Entity)
+                .and_where(user_role::Column::RoleId.eq(role_id))
+                .to_owned();
+            condition = condition.add(UserColumn::Id.in_subquery(users));
+        }
+        let (column, order) = order;
+        // 排序字段相同时按ID排序，保证分页稳定
+        let query = UserEntity::find().filter(condition).order_by(column, order).order_by_asc(UserColumn::Id);
+        let paginator = query.paginate(db, size);
+        let total = paginator.num_items().await?;
+        let users = paginator.fetch_page(page).await?;
+        Ok((users, total))
+    }
+
+    pub async fn insert<C: ConnectionTrait>(db: &C, model: UserActiveModel) -> Result<UserModel, DbErr> {
+        model.insert(db).await
+    }
+
+    pub async fn update<C: ConnectionTrait>(db: &C, model: UserActiveModel) -> Result<UserModel, DbErr> {
+        model.update(db).await
+    }
+
+    /// 登录失败次数加1，返回加1后的次数
+    pub async fn increase_failed_logins(db: &DbConn, id: i64) -> Result<i32, DbErr> {
+        UserEntity::update_many()
+            .col_expr(UserColumn::FailedLogins, Expr::col(UserColumn::FailedLogins).add(1))
+            .filter(UserColumn::Id.eq(id))
+            .exec(db)
+            .await?;
+        Ok(Self::find_by_id(db, id).await?.map(|u| u.failed_logins).unwrap_or_default())
+    }
+
+    /// 锁定到`until`，同时清零失败次数
+    pub async fn lock(db: &DbConn, id: i64, until: DateTimeUtc) -> Result<(), DbErr> {
+        UserEntity::update_many()
+            .col_expr(UserColumn::FailedLogins, Expr::value(0))
+            .col_expr(UserColumn::LockedUntil, Expr::value(until))
+            .filter(UserColumn::Id.eq(id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// 同时删除用户的角色及令牌
+    pub async fn delete<C: ConnectionTrait>(db: &C, id: i64) -> Result<(), DbErr> {
+        user_role::Entity::delete_many().filter(user_role::Column::UserId.eq(id)).exec(db).await?;
+        user_token::Entity::delete_many().filter(user_token::Column::UserId.eq(id)).exec(db).await?;
+        UserEntity::delete_by_id(id).exec(db).await?;
+        Ok(())
+    }
+
+    /// 用户的角色ID
+    pub async fn find_role_ids<C: ConnectionTrait>(db: &C, user_ids: &[i64]) -> Result<HashMap<i64, Vec<i64>>, DbErr> {
+        let rows = user_role::Entity::find()
+            .filter(user_role::Column::UserId.is_in(user_ids.iter().copied()))
+            .order_by_asc(user_role::Column::RoleId)
+            .all(db)
+            .await?;
+        let mut roles: HashMap<i64, Vec<i64>> = HashMap::new();
+        for row in rows {
+            roles.entry(row.user_id).or_default().push(row.role_id);
+        }
+        Ok(roles)
+    }
+
+    /// 替换用户的所有角色
+    pub async fn set_roles<C: ConnectionTrait>(db: &C, user_id: i64, role_ids: &[i64]) -> Result<(), DbErr> {
+        user_role::Entity::delete_many().filter(user_role::Column::UserId.eq(user_id)).exec(db).await?;
+        if role_ids.is_empty() {
+            return Ok(());
+        }
+        let rows = role_ids.iter().map(|&role_id| user_role::ActiveModel {
+            user_id: Set(user_id),
+            role_id: Set(role_id),
+            ..Default::default()
+        });
+        user_role::Entity::insert_many(rows).exec(db).await?;
+        Ok(())
+    }
+}
+
+pub struct RoleRepository;
+
+impl RoleRepository {
+    pub async fn find_by_ids(db: &DbConn, ids: &[i64]) -> Result<Vec<RoleModel>, DbErr> {
+        RoleEntity::find().filter(role::Column::Id.is_in(ids.iter().copied())).all(db).await
+    }
+
+    pub async fn find_by_names<C: ConnectionTrait>(db: &C, names: &[String]) -> Result<Vec<RoleModel>, DbErr> {
+        RoleEntity::find().filter(role::Column::Name.is_in(names.iter().cloned())).all(db).await
+    }
+
+    pub async fn insert<C: ConnectionTrait>(db: &C, name: &str) -> Result<RoleModel, DbErr> {
+        role::ActiveModel { name: Set(name.to_string()), ..Default::default() }.insert(db).await
+    }
+
+    /// 替换角色的所有权限
+    pub async fn set_permissions<C: ConnectionTrait>(db: &C, role_id: i64, permission_ids: &[i64]) -> Result<(), DbErr> {
+        role_permission::Entity::delete_many().filter(role_permission::Column::RoleId.eq(role_id)).exec(db).await?;
+        if permission_ids.is_empty() {
+            return Ok(());
+        }
+        let rows = permission_ids.iter().map(|&permission_id| role_permission::ActiveModel {
+            role_id: Set(role_id),
+            permission_id: Set(permission_id),
+            ..Default::default()
+        });
+        role_permission::Entity::insert_many(rows).exec(db).await?;
+        Ok(())
+    }
+}
+
+pub struct PermissionRepository;
+
+impl PermissionRepository {
+    pub async fn find_all<C: ConnectionTrait>(db: &C) -> Result<Vec<PermissionModel>, DbErr> {
+        PermissionEntity::find().order_by_asc(permission::Column::Code).all(db).await
+    }
+
+    /// 用户所有角色的权限
+    pub async fn find_by_user(db: &DbConn, user_id: i64) -> Result<Vec<PermissionModel>, DbErr> {
+        let roles = Query::select()
+            .column(user_role::Column::RoleId)
+            .from(user_role::Entity)
+            .and_where(user_role::Column::UserId.eq(user_id))
+            .to_owned();
+        let permissions = Query::select()
+            .column(role_permission::Column::PermissionId)
+            .from(role_permission::Entity)
+            .and_where(role_permission::Column::RoleId.in_subquery(roles))
+            .to_owned();
+        PermissionEntity::find()
+            .filter(permission::Column::Id.in_subquery(permissions))
+            .order_by_asc(permission::Column::Code)
+            .all(db)
+            .await
+    }
+}
+
+pub struct UserTokenRepository;
+
+impl UserTokenRepository {
+    /// 保存新的令牌，同时作废该用户同一用途未使用的令牌
+    pub async fn issue<C: ConnectionTrait>(db: &C, model: user_token::ActiveModel, now: DateTimeUtc) -> Result<user_token::Model, DbErr> {
+        Self::invalidate(db, model.user_id.clone().unwrap(), &model.purpose.clone().unwrap(), now).await?;
+        model.insert(db).await
+    }
+
+    /// 保存新的令牌，不影响该用户同一用途的其他令牌，用于多个客户端同时登录的刷新令牌
+    pub async fn save<C: ConnectionTrait>(db: &C, model: user_token::ActiveModel) -> Result<user_token::Model, DbErr> {
+        model.insert(db).await
+    }
+
+    /// 作废用户某一用途未使用的令牌
+    pub async fn invalidate<C: ConnectionTrait>(db: &C, user_id: i64, purpose: &str, now: DateTimeUtc) -> Result<(), DbErr> {
+        user_token::Entity::update_many()
+            .col_expr(user_token::Column::UsedAt, Expr::value(now))
+            .filter(user_token::Column::UserId.eq(user_id))
+            .filter(user_token::Column::Purpose.eq(purpose))
+            .filter(user_token::Column::UsedAt.is_null())
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// 未使用且未过期的令牌
+    pub async fn find_valid(db: &DbConn, purpose: &str, token_hash: &str, now: DateTimeUtc) -> Result<Option<user_token::Model>, DbErr> {
+        user_token::Entity::find()
+            .filter(user_token::Column::TokenHash.eq(token_hash))
+            .filter(user_token::Column::Purpose.eq(purpose))
+            .filter(user_token::Column::UsedAt.is_null())
+            .filter(user_token::Column::ExpiresAt.gt(now))
+            .one(db)
+            .await
+    }
+
+    /// 标记令牌已使用，并发使用同一令牌时只有一个返回`true`
+    pub async fn consume<C: ConnectionTrait>(db: &C, id: i64, now: DateTimeUtc) -> Result<bool, DbErr> {
+        let result = user_token::Entity::update_many()
+            .col_expr(user_token::Column::UsedAt, Expr::value(now))
+            .filter(user_token::Column::Id.eq(id))
+            .filter(user_token::Column::UsedAt.is_null())
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+}
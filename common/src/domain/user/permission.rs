@@ -0,0 +1,19 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::{DeriveEntityModel, EnumIter};
+
+/// 权限，`code`即接口声明的权限，eg:`user:read`
+#[derive(Clone, Debug, DeriveEntityModel)]
+#[sea_orm(table_name = "permission")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    #[sea_orm(unique)]
+    pub code: String,
+    pub description: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {}
@@ -5,7 +5,7 @@ use sea_orm::{DeriveEntityModel, EnumIter};
 #[sea_orm(table_name = "role")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
+    pub id: i64,
     pub name: String,
 }
 
@@ -13,4 +13,4 @@ pub struct Model {
 pub enum Relation {}
 
 #[async_trait::async_trait]
-impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
+impl ActiveModelBehavior for ActiveModel {}
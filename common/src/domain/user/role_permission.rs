@@ -0,0 +1,36 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::{DeriveEntityModel, EntityTrait, EnumIter, RelationDef, RelationTrait};
+use crate::domain::user::{permission, role};
+
+#[derive(Clone, Debug, DeriveEntityModel)]
+#[sea_orm(table_name = "role_permission")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub role_id: i64,
+    pub permission_id: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Role,
+    Permission,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::Role => Entity::belongs_to(role::Entity)
+                .from(Column::RoleId)
+                .to(role::Column::Id)
+                .into(),
+            Relation::Permission => Entity::belongs_to(permission::Entity)
+                .from(Column::PermissionId)
+                .to(permission::Column::Id)
+                .into(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {}
@@ -10,6 +10,15 @@ pub struct Model {
     pub password: String,
     pub email: String,
     pub phone: String,
+    /// 连续登录失败的次数，锁定后清零
+    #[sea_orm(default_value = 0)]
+    pub failed_logins: i32,
+    /// 锁定截止时间，之前不允许登录
+    pub locked_until: Option<DateTimeUtc>,
+    /// 最后修改密码的时间
+    pub password_changed_at: Option<DateTimeUtc>,
+    /// 验证邮箱的时间，未验证时不能登录
+    pub email_verified_at: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -6,9 +6,9 @@ use crate::domain::user::{role, user};
 #[sea_orm(table_name = "user_role")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
-    pub user_id: String,
-    pub role_id: String,
+    pub id: i64,
+    pub user_id: i64,
+    pub role_id: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter)]
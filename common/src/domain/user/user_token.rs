@@ -0,0 +1,33 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::{DeriveEntityModel, EnumIter};
+
+/// 验证邮箱的令牌
+pub const PURPOSE_VERIFY_EMAIL: &str = "verify_email";
+/// 重置密码的令牌
+pub const PURPOSE_RESET_PASSWORD: &str = "reset_password";
+/// 刷新令牌，保存的是JWT中`jti`的摘要
+pub const PURPOSE_REFRESH: &str = "refresh";
+
+/// 一次性令牌：邮件中的令牌及刷新令牌，只保存令牌的摘要
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "user_token")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub user_id: i64,
+    /// 令牌用途
+    pub purpose: String,
+    /// 令牌的SHA-256摘要（hex）
+    #[sea_orm(unique)]
+    pub token_hash: String,
+    pub expires_at: DateTimeUtc,
+    /// 使用或作废的时间，不为空时不能再使用
+    pub used_at: Option<DateTimeUtc>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,121 @@
+use std::fmt::{Debug, Formatter};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use lettre::message::Mailbox;
+use serde::Deserialize;
+
+/// 邮件配置，修改后需要重启生效
+#[serde_with::serde_as]
+#[derive(Clone, Deserialize, PartialEq)]
+pub struct EmailConfig {
+    /// 发送方式：`smtp`(默认)/`sendmail`/`file`
+    #[serde(default)]
+    pub transport: TransportKind,
+    /// SMTP服务器地址
+    #[serde(default)]
+    pub relay: Option<String>,
+    /// SMTP端口，不配置时`tls`为465，`starttls`为587，`none`为25
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// `none`/`starttls`(默认)/`tls`
+    #[serde(default)]
+    pub tls: TlsMode,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// 连接SMTP服务器的超时时间，默认10秒
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_timeout")]
+    pub timeout: Duration,
+    /// sendmail命令，默认为`PATH`中的`sendmail`
+    #[serde(default)]
+    pub sendmail_command: Option<String>,
+    /// `file`时保存邮件的目录
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+    /// 默认发件人，eg:`应用 <noreply@example.com>`
+    pub from: String,
+    /// 默认回复地址
+    #[serde(default)]
+    pub reply_to: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportKind {
+    #[default]
+    Smtp,
+    Sendmail,
+    File,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TlsMode {
+    /// 不加密，只应在内网使用
+    None,
+    /// 连接后升级为TLS
+    #[default]
+    Starttls,
+    /// 直接使用TLS连接
+    Tls,
+}
+
+/// 输出时隐藏密码
+impl Debug for EmailConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmailConfig")
+            .field("transport", &self.transport)
+            .field("relay", &self.relay)
+            .field("port", &self.port)
+            .field("tls", &self.tls)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| crate::REDACTED))
+            .field("timeout", &self.timeout)
+            .field("sendmail_command", &self.sendmail_command)
+            .field("dir", &self.dir)
+            .field("from", &self.from)
+            .field("reply_to", &self.reply_to)
+            .finish()
+    }
+}
+
+impl EmailConfig {
+    /// 校验配置，返回出错的配置项及原因
+    pub fn validate(&self) -> Vec<(&'static str, String)> {
+        let mut errors = Vec::new();
+        if let Err(e) = self.from.parse::<Mailbox>() {
+            errors.push(("from", format!("is not a valid mailbox: {}", e)));
+        }
+        if let Some(Err(e)) = self.reply_to.as_ref().map(|r| r.parse::<Mailbox>()) {
+            errors.push(("reply_to", format!("is not a valid mailbox: {}", e)));
+        }
+        match self.transport {
+            TransportKind::Smtp => {
+                if self.relay.as_deref().is_none_or(|r| r.trim().is_empty()) {
+                    errors.push(("relay", "is required by smtp".to_string()));
+                }
+                if self.username.is_some() != self.password.is_some() {
+                    errors.push(("password", "username and password must be configured together".to_string()));
+                }
+            }
+            TransportKind::File if self.dir.is_none() => errors.push(("dir", "is required by file".to_string())),
+            _ => {}
+        }
+        errors
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(match self.tls {
+            TlsMode::None => 25,
+            TlsMode::Starttls => 587,
+            TlsMode::Tls => 465,
+        })
+    }
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(10)
+}
@@ -1,64 +1,149 @@
+//! 邮件发送，发送方式由`email`配置决定，测试时使用内存或文件代替SMTP
+
+use std::sync::Arc;
+
+use lettre::message::header::{ContentTransferEncoding, ContentType};
+use lettre::message::{Mailbox, MultiPart, SinglePart};
+use thiserror::Error;
+
+pub mod config;
+pub mod template;
+pub mod transport;
+
+pub use config::EmailConfig;
+/// 自定义发送方式时使用
+pub use lettre::Message;
+pub use template::{Rendered, Template};
+pub use transport::{DisabledTransport, EmailTransport, FileTransport, MemoryTransport, SendmailTransport, SentEmail, SmtpTransport};
+
+#[derive(Debug, Error)]
+pub enum EmailError {
+    #[error("invalid email address: {0}")]
+    Address(#[from] lettre::address::AddressError),
+
+    #[error("{0}")]
+    Message(#[from] lettre::error::Error),
+
+    #[error("send email fail: {0}")]
+    Transport(String),
+
+    #[error("invalid email template: {0}")]
+    Template(String),
+
+    #[error("invalid email config: {0}")]
+    Config(String),
+}
+
+/// 使用默认的发件人及回复地址发送模板邮件
+pub struct EmailService {
+    transport: Arc<dyn EmailTransport>,
+    from: Mailbox,
+    reply_to: Option<Mailbox>,
+}
+
+impl EmailService {
+    /// 按配置创建发送方式
+    pub fn new(config: &EmailConfig) -> Result<Self, EmailError> {
+        Self::with_transport(transport::build(config)?, &config.from, config.reply_to.as_deref())
+    }
+
+    pub fn with_transport(transport: Arc<dyn EmailTransport>, from: &str, reply_to: Option<&str>) -> Result<Self, EmailError> {
+        Ok(Self {
+            transport,
+            from: from.parse()?,
+            reply_to: reply_to.map(str::parse).transpose()?,
+        })
+    }
+
+    /// 渲染模板后发送
+    pub async fn send(&self, to: &str, template: &Template, vars: &[(&str, &str)]) -> Result<(), EmailError> {
+        self.send_rendered(to, template.render(vars)?).await
+    }
+
+    /// 有HTML时同时发送纯文本及HTML两个版本
+    pub async fn send_rendered(&self, to: &str, rendered: Rendered) -> Result<(), EmailError> {
+        let message = self.build(to, rendered.clone())?;
+        let email = SentEmail {
+            to: message.envelope().to().iter().map(|a| a.to_string()).collect(),
+            subject: rendered.subject,
+            text: rendered.text,
+            html: rendered.html,
+        };
+        self.transport.send_email(message, email).await
+    }
+
+    /// 生成邮件，正文统一使用`base64`编码
+    pub fn build(&self, to: &str, rendered: Rendered) -> Result<Message, EmailError> {
+        let mut builder = Message::builder().from(self.from.clone()).to(to.parse()?).subject(rendered.subject);
+        if let Some(reply_to) = &self.reply_to {
+            builder = builder.reply_to(reply_to.clone());
+        }
+        let text = part(ContentType::TEXT_PLAIN, rendered.text);
+        let message = match rendered.html {
+            Some(html) => builder.multipart(MultiPart::alternative().singlepart(text).singlepart(part(ContentType::TEXT_HTML, html)))?,
+            None => builder.singlepart(text)?,
+        };
+        Ok(message)
+    }
+}
+
+fn part(content_type: ContentType, body: String) -> SinglePart {
+    SinglePart::builder().header(content_type).header(ContentTransferEncoding::Base64).body(body)
+}
+
 #[cfg(test)]
 mod tests {
-    use lettre::message::header::ContentType;
-    use lettre::transport::smtp::authentication::Credentials;
-    use lettre::{Message, SmtpTransport, Transport};
-
-    #[test]
-    fn test_email() {
-
-        // 创建邮件内容
-        let email = Message::builder()
-            .from("Your Name <office@bonviewpress.com>".parse().unwrap())
-            .reply_to("hnbcao@qq.com".parse().unwrap())
-            .to("Recipient Name <hnbcao@qq.com>".parse().unwrap())
-            .subject("Rust Email")
-            .header(ContentType::TEXT_PLAIN)
-            .body(String::from("Hello, this is a test email from Rust!"))
-            .unwrap();
-
-        // 设置 SMTP 客户端的认证信息 dhykhdydgaiscbed
-        let creds = Credentials::new("office@bonviewpress.com".to_owned(), "AfjvEBnAfwmmQ32C".to_owned());
-
-        // 建立到 SMTP 服务器的连接
-        let mailer = SmtpTransport::relay("smtp.exmail.qq.com")
-            .unwrap()
-            .credentials(creds)
-            .build();
-
-        // Send the email
-        match mailer.send(&email) {
-            Ok(_) => println!("Email sent successfully!"),
-            Err(e) => panic!("Could not send email: {e:?}"),
-        }
+    use super::*;
+
+    fn welcome() -> Template {
+        Template::new("欢迎 {{name}}", "你好 {{name}}，请访问 {{link}}").html("<p>你好 {{name}}，请访问 <a href=\"{{link}}\">链接</a></p>")
     }
 
-    #[test]
-    fn test_email2() {
-
-        // 创建邮件内容
-        let email = Message::builder()
-            .from("Your Name <hnbcao@foxmail.com>".parse().unwrap())
-            .reply_to("hnbcao@qq.com".parse().unwrap())
-            .to("Recipient Name <hnbcao@qq.com>".parse().unwrap())
-            .subject("Rust Email")
-            .header(ContentType::TEXT_PLAIN)
-            .body(String::from("Hello, this is a test email from Rust!"))
-            .unwrap();
-
-        // 设置 SMTP 客户端的认证信息 dhykhdydgaiscbed
-        let creds = Credentials::new("hnbcao@foxmail.com".to_owned(), "dhykhdydgaiscbed".to_owned());
-
-        // 建立到 SMTP 服务器的连接
-        let mailer = SmtpTransport::relay("smtp.qq.com")
-            .unwrap()
-            .credentials(creds)
-            .build();
-
-        // Send the email
-        match mailer.send(&email) {
-            Ok(_) => println!("Email sent successfully!"),
-            Err(e) => panic!("Could not send email: {e:?}"),
-        }
+    #[tokio::test]
+    async fn test_memory_transport() {
+        let transport = Arc::new(MemoryTransport::default());
+        let service = EmailService::with_transport(transport.clone(), "应用 <noreply@example.com>", Some("support@example.com")).unwrap();
+        service.send("alice@example.com", &welcome(), &[("name", "<Alice>"), ("link", "https://example.com/?a=1&b=2")]).await.unwrap();
+        service.send_rendered("bob@example.com", Template::new("纯文本", "正文").render(&[]).unwrap()).await.unwrap();
+
+        let emails = transport.emails();
+        assert_eq!(emails.len(), 2);
+        assert_eq!(emails[0].to, vec!["alice@example.com".to_string()]);
+        assert_eq!(emails[0].subject, "欢迎 <Alice>");
+        assert_eq!(emails[0].text, "你好 <Alice>，请访问 https://example.com/?a=1&b=2");
+        assert_eq!(
+            emails[0].html.as_deref(),
+            Some("<p>你好 &lt;Alice&gt;，请访问 <a href=\"https://example.com/?a=1&amp;b=2\">链接</a></p>")
+        );
+        assert_eq!((emails[1].subject.as_str(), emails[1].text.as_str(), emails[1].html.as_deref()), ("纯文本", "正文", None));
+        let formatted = String::from_utf8(transport.messages()[0].formatted()).unwrap();
+        assert!(formatted.contains("Reply-To: support@example.com"));
+
+        let error = service.send("alice@example.com", &welcome(), &[("name", "Alice")]).await.unwrap_err();
+        assert_eq!(error.to_string(), "invalid email template: variable `link` is not provided");
+        assert!(service.send("not an address", &Template::new("a", "b"), &[]).await.is_err());
+        assert_eq!(transport.messages().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_file_transport() {
+        let dir = std::env::temp_dir().join(format!("email-test-{}", uuid::Uuid::new_v4().simple()));
+        let config: EmailConfig = serde_json::from_value(serde_json::json!({
+            "transport": "file",
+            "dir": dir,
+            "from": "noreply@example.com",
+        }))
+        .unwrap();
+        assert!(config.validate().is_empty());
+        EmailService::new(&config).unwrap().send("alice@example.com", &welcome(), &[("name", "Alice"), ("link", "x")]).await.unwrap();
+        let files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().path()).collect();
+        assert_eq!(files.len(), 1);
+        assert!(std::fs::read_to_string(&files[0]).unwrap().contains("To: alice@example.com"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let smtp: EmailConfig = serde_json::from_value(serde_json::json!({"from": "bad", "username": "u"})).unwrap();
+        let fields: Vec<_> = smtp.validate().into_iter().map(|(field, _)| field).collect();
+        assert_eq!(fields, vec!["from", "relay", "password"]);
+        assert!(!format!("{:?}", EmailConfig { password: Some("secret".to_string()), ..smtp }).contains("secret"));
     }
-}
\ No newline at end of file
+}
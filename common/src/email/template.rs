@@ -0,0 +1,73 @@
+use crate::email::EmailError;
+
+/// 邮件模板，`{{name}}`为变量，HTML中的变量会转义
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    pub subject: String,
+    pub text: String,
+    pub html: Option<String>,
+}
+
+/// 替换变量后的邮件内容
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rendered {
+    pub subject: String,
+    pub text: String,
+    pub html: Option<String>,
+}
+
+impl Template {
+    pub fn new(subject: impl Into<String>, text: impl Into<String>) -> Self {
+        Self { subject: subject.into(), text: text.into(), html: None }
+    }
+
+    pub fn html(mut self, html: impl Into<String>) -> Self {
+        self.html = Some(html.into());
+        self
+    }
+
+    /// 模板中的变量都需要提供，否则返回错误
+    pub fn render(&self, vars: &[(&str, &str)]) -> Result<Rendered, EmailError> {
+        Ok(Rendered {
+            subject: substitute(&self.subject, vars, false)?,
+            text: substitute(&self.text, vars, false)?,
+            html: self.html.as_deref().map(|html| substitute(html, vars, true)).transpose()?,
+        })
+    }
+}
+
+fn substitute(template: &str, vars: &[(&str, &str)], escape: bool) -> Result<String, EmailError> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find("}}") else {
+            return Err(EmailError::Template("unclosed `{{`".to_string()));
+        };
+        let name = rest[start + 2..start + end].trim();
+        let Some((_, value)) = vars.iter().find(|(n, _)| *n == name) else {
+            return Err(EmailError::Template(format!("variable `{}` is not provided", name)));
+        };
+        if escape {
+            escape_html(value, &mut output);
+        } else {
+            output.push_str(value);
+        }
+        rest = &rest[start + end + 2..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+fn escape_html(value: &str, output: &mut String) {
+    for c in value.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            _ => output.push(c),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncFileTransport, AsyncSendmailTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::email::config::{EmailConfig, TlsMode, TransportKind};
+use crate::email::EmailError;
+
+/// 邮件的发送方式
+#[async_trait::async_trait]
+pub trait EmailTransport: Send + Sync {
+    async fn send(&self, message: Message) -> Result<(), EmailError>;
+
+    /// [`EmailService`](crate::email::EmailService)发送时调用，`email`为生成邮件前的内容，默认只发送`message`
+    async fn send_email(&self, message: Message, email: SentEmail) -> Result<(), EmailError> {
+        let _ = email;
+        self.send(message).await
+    }
+}
+
+/// 按配置创建发送方式
+pub fn build(config: &EmailConfig) -> Result<Arc<dyn EmailTransport>, EmailError> {
+    Ok(match config.transport {
+        TransportKind::Smtp => Arc::new(SmtpTransport::new(config)?),
+        TransportKind::Sendmail => Arc::new(SendmailTransport::new(config.sendmail_command.as_deref())),
+        TransportKind::File => {
+            let dir = config.dir.as_deref().ok_or_else(|| EmailError::Config("dir is required by file".to_string()))?;
+            Arc::new(FileTransport::new(dir).map_err(|e| EmailError::Config(format!("can not create {}: {}", dir.display(), e)))?)
+        }
+    })
+}
+
+async fn send_with<T: AsyncTransport + Sync>(transport: &T, message: Message) -> Result<(), EmailError>
+where
+    T::Error: std::fmt::Display,
+{
+    transport.send(message).await.map(|_| ()).map_err(|e| EmailError::Transport(e.to_string()))
+}
+
+/// 通过SMTP服务器发送，连接由连接池复用
+pub struct SmtpTransport {
+    inner: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpTransport {
+    pub fn new(config: &EmailConfig) -> Result<Self, EmailError> {
+        let relay = config.relay.as_deref().ok_or_else(|| EmailError::Config("relay is required by smtp".to_string()))?;
+        let mut builder = match config.tls {
+            TlsMode::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(relay),
+            TlsMode::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(relay).map_err(|e| EmailError::Config(e.to_string()))?,
+            TlsMode::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(relay).map_err(|e| EmailError::Config(e.to_string()))?,
+        };
+        builder = builder.port(config.port()).timeout(Some(config.timeout));
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(Self { inner: builder.build() })
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailTransport for SmtpTransport {
+    async fn send(&self, message: Message) -> Result<(), EmailError> {
+        send_with(&self.inner, message).await
+    }
+}
+
+/// 通过本机的sendmail命令发送
+pub struct SendmailTransport {
+    inner: AsyncSendmailTransport<Tokio1Executor>,
+}
+
+impl SendmailTransport {
+    pub fn new(command: Option<&str>) -> Self {
+        let inner = match command {
+            Some(command) => AsyncSendmailTransport::new_with_command(command),
+            None => AsyncSendmailTransport::new(),
+        };
+        Self { inner }
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailTransport for SendmailTransport {
+    async fn send(&self, message: Message) -> Result<(), EmailError> {
+        send_with(&self.inner, message).await
+    }
+}
+
+/// 每封邮件保存为目录下的一个`.eml`文件
+pub struct FileTransport {
+    inner: AsyncFileTransport<Tokio1Executor>,
+}
+
+impl FileTransport {
+    pub fn new(dir: impl AsRef<Path>) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir.as_ref())?;
+        Ok(Self { inner: AsyncFileTransport::new(dir) })
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailTransport for FileTransport {
+    async fn send(&self, message: Message) -> Result<(), EmailError> {
+        send_with(&self.inner, message).await
+    }
+}
+
+/// 未配置邮件时使用，发送时返回错误，邮件保留在发件箱中
+pub struct DisabledTransport;
+
+#[async_trait::async_trait]
+impl EmailTransport for DisabledTransport {
+    async fn send(&self, _message: Message) -> Result<(), EmailError> {
+        Err(EmailError::Config("email is not configured".to_string()))
+    }
+}
+
+/// 邮件保存在内存中，用于测试
+#[derive(Default)]
+pub struct MemoryTransport {
+    messages: Mutex<Vec<Message>>,
+    emails: Mutex<Vec<SentEmail>>,
+}
+
+/// 生成邮件前的收件人、标题及正文，用于测试断言
+#[derive(Clone, Debug, PartialEq)]
+pub struct SentEmail {
+    pub to: Vec<String>,
+    pub subject: String,
+    pub text: String,
+    pub html: Option<String>,
+}
+
+impl MemoryTransport {
+    /// 已发送的邮件
+    pub fn messages(&self) -> Vec<Message> {
+        self.messages.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 通过[`EmailService`](crate::email::EmailService)发送的邮件
+    pub fn emails(&self) -> Vec<SentEmail> {
+        self.emails.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailTransport for MemoryTransport {
+    async fn send(&self, message: Message) -> Result<(), EmailError> {
+        self.messages.lock().unwrap_or_else(|e| e.into_inner()).push(message);
+        Ok(())
+    }
+
+    async fn send_email(&self, message: Message, email: SentEmail) -> Result<(), EmailError> {
+        self.send(message).await?;
+        self.emails.lock().unwrap_or_else(|e| e.into_inner()).push(email);
+        Ok(())
+    }
+}
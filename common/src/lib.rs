@@ -1,10 +1,12 @@
 pub mod domain;
 pub mod email;
-pub mod env;
-mod errors;
+pub mod errors;
 pub mod migration;
 pub mod queue;
 
+/// 日志、`Debug`中代替密码等敏感信息
+pub const REDACTED: &str = "******";
+
 pub fn add(left: usize, right: usize) -> usize {
     left + right
 }
@@ -0,0 +1,107 @@
+//! 多个实例同时启动时只有一个实例执行迁移：
+//! - MySQL：`GET_LOCK`，锁名包含数据库名
+//! - Postgres：`pg_advisory_xact_lock`，事务结束时释放
+//! - SQLite：数据库文件旁的`.migration.lock`文件锁，内存数据库不加锁
+
+use std::fs::File;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use fs4::fs_std::FileExt;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DatabaseTransaction, DbBackend, DbErr, Statement, TransactionTrait};
+
+/// Postgres咨询锁的键
+const PG_LOCK_KEY: i64 = 0x5345_414f_524d_4d47;
+/// 获取SQLite文件锁的重试间隔
+const FILE_LOCK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 持有期间其他实例不能执行迁移，需要调用[`MigrationLock::release`]释放
+pub struct MigrationLock {
+    inner: Inner,
+}
+
+enum Inner {
+    /// 锁与连接绑定，使用事务占用一个连接
+    Session(DatabaseTransaction),
+    /// 文件关闭时也会释放
+    File(File),
+    None,
+}
+
+impl MigrationLock {
+    /// 等待获取锁，超过`timeout`时返回错误；`url`用于定位SQLite数据库文件
+    pub async fn acquire(db: &DatabaseConnection, url: &str, timeout: Duration) -> Result<Self, DbErr> {
+        let inner = match db.get_database_backend() {
+            DbBackend::MySql => {
+                let txn = db.begin().await?;
+                let stmt = Statement::from_sql_and_values(
+                    DbBackend::MySql,
+                    "SELECT GET_LOCK(CONCAT(DATABASE(), '.seaql_migrations'), ?) AS locked",
+                    [timeout.as_secs().into()],
+                );
+                let locked: Option<i64> = match txn.query_one(stmt).await? {
+                    Some(row) => row.try_get("", "locked")?,
+                    None => None,
+                };
+                if locked != Some(1) {
+                    return Err(timeout_error(timeout));
+                }
+                Inner::Session(txn)
+            }
+            DbBackend::Postgres => {
+                let txn = db.begin().await?;
+                let set_timeout = format!("SET LOCAL lock_timeout = '{}ms'", timeout.as_millis());
+                txn.execute_unprepared(&set_timeout).await?;
+                let stmt = Statement::from_sql_and_values(DbBackend::Postgres, "SELECT pg_advisory_xact_lock($1)", [PG_LOCK_KEY.into()]);
+                txn.execute(stmt).await.map_err(|e| DbErr::Custom(format!("{}: {}", timeout_error(timeout), e)))?;
+                Inner::Session(txn)
+            }
+            DbBackend::Sqlite => match sqlite_lock_path(url) {
+                Some(path) => Inner::File(lock_file(path, timeout).await?),
+                None => Inner::None,
+            },
+        };
+        Ok(Self { inner })
+    }
+
+    pub async fn release(self) -> Result<(), DbErr> {
+        match self.inner {
+            Inner::Session(txn) => {
+                if txn.get_database_backend() == DbBackend::MySql {
+                    txn.execute_unprepared("DO RELEASE_LOCK(CONCAT(DATABASE(), '.seaql_migrations'))").await?;
+                }
+                txn.commit().await
+            }
+            Inner::File(file) => FileExt::unlock(&file).map_err(|e| DbErr::Custom(format!("can not unlock migration lock: {}", e))),
+            Inner::None => Ok(()),
+        }
+    }
+}
+
+fn timeout_error(timeout: Duration) -> DbErr {
+    DbErr::Custom(format!("can not acquire migration lock in {:?}, another instance may be migrating", timeout))
+}
+
+/// `sqlite://path/to/db.sqlite?mode=rwc`的锁文件为`path/to/db.sqlite.migration.lock`
+fn sqlite_lock_path(url: &str) -> Option<PathBuf> {
+    let path = url.strip_prefix("sqlite:")?;
+    let path = path.strip_prefix("//").unwrap_or(path);
+    let path = path.split('?').next().unwrap_or_default();
+    if path.is_empty() || path == ":memory:" || url.contains("mode=memory") {
+        return None;
+    }
+    Some(PathBuf::from(format!("{}.migration.lock", path)))
+}
+
+async fn lock_file(path: PathBuf, timeout: Duration) -> Result<File, DbErr> {
+    let io_error = |e: std::io::Error| DbErr::Custom(format!("can not lock {}: {}", path.display(), e));
+    let file = File::options().create(true).truncate(false).write(true).open(&path).map_err(io_error)?;
+    let start = Instant::now();
+    while !FileExt::try_lock_exclusive(&file).map_err(io_error)? {
+        if start.elapsed() >= timeout {
+            return Err(timeout_error(timeout));
+        }
+        tokio::time::sleep(FILE_LOCK_INTERVAL).await;
+    }
+    Ok(file)
+}
@@ -1,59 +1,79 @@
-use sea_orm::DbBackend;
 use sea_orm_migration::async_trait::async_trait;
 use sea_orm_migration::prelude::*;
 
 #[derive(DeriveMigrationName)]
 pub struct Migration;
 
+/// 用户、角色及用户角色，使用schema builder以支持MySQL、Postgres及SQLite
 #[async_trait]
 impl MigrationTrait for Migration {
     async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
-        let database_backend = manager.get_database_backend();
-        let db = manager.get_connection();
-        match database_backend {
-            DbBackend::MySql => db.execute_unprepared(MYSQL_MIGRATION_UP_DDL).await?,
-            DbBackend::Postgres => db.execute_unprepared(MYSQL_MIGRATION_UP_DDL).await?,
-            DbBackend::Sqlite => db.execute_unprepared(MYSQL_MIGRATION_UP_DDL).await?,
-        };
-        Ok(())
+        manager
+            .create_table(
+                Table::create()
+                    .table(User::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(User::Id).big_integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(User::Username).string_len(255).null())
+                    .col(ColumnDef::new(User::Password).string_len(255).null())
+                    .col(ColumnDef::new(User::Email).string_len(255).null())
+                    .col(ColumnDef::new(User::Phone).string_len(20).null())
+                    .index(Index::create().name("pk_phone").col(User::Phone).unique())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(Role::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Role::Id).big_integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(Role::Name).string_len(255).null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserRole::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(UserRole::Id).big_integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(UserRole::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(UserRole::RoleId).big_integer().not_null())
+                    .index(Index::create().name("pk_user_id_role_id").col(UserRole::UserId).col(UserRole::RoleId).unique())
+                    .to_owned(),
+            )
+            .await
     }
 
     async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
-        let database_backend = manager.get_database_backend();
-        let db = manager.get_connection();
-        match database_backend {
-            DbBackend::MySql => db.execute_unprepared(MYSQL_MIGRATION_DOWN_DDL).await?,
-            DbBackend::Postgres => db.execute_unprepared(MYSQL_MIGRATION_DOWN_DDL).await?,
-            DbBackend::Sqlite => db.execute_unprepared(MYSQL_MIGRATION_DOWN_DDL).await?,
-        };
-        Ok(())
+        manager.drop_table(Table::drop().table(UserRole::Table).if_exists().to_owned()).await?;
+        manager.drop_table(Table::drop().table(Role::Table).if_exists().to_owned()).await?;
+        manager.drop_table(Table::drop().table(User::Table).if_exists().to_owned()).await
     }
 }
 
-const MYSQL_MIGRATION_UP_DDL: &str = r#"CREATE TABLE IF NOT EXISTS `user` (
-  `id` int(11) NOT NULL,
-  `username` varchar(255) DEFAULT NULL,
-  `password` varchar(255) DEFAULT NULL,
-  `email` varchar(255) DEFAULT NULL,
-  `phone` varchar(20) DEFAULT NULL,
-  PRIMARY KEY (`id`),
-  UNIQUE KEY `pk_phone` (`phone`) USING BTREE
-);
-
-CREATE TABLE IF NOT EXISTS `role` (
-  `id` int(11) NOT NULL,
-  `name` varchar(255) DEFAULT NULL,
-  PRIMARY KEY (`id`)
-);
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+    Username,
+    Password,
+    Email,
+    Phone,
+}
 
-CREATE TABLE IF NOT EXISTS `user_role` (
-  `id` int(11) NOT NULL,
-  `user_id` int(11) DEFAULT NULL,
-  `role_id` int(11) DEFAULT NULL,
-  PRIMARY KEY (`id`),
-  UNIQUE KEY `pk_user_id_role_id` (`user_id`,`role_id`) USING BTREE
-);"#;
+#[derive(DeriveIden)]
+enum Role {
+    Table,
+    Id,
+    Name,
+}
 
-const MYSQL_MIGRATION_DOWN_DDL: &str = r#"DROP TABLE IF EXISTS `user_role`;
-DROP TABLE IF EXISTS `role`;
-DROP TABLE IF EXISTS `user`;"#;
\ No newline at end of file
+#[derive(DeriveIden)]
+enum UserRole {
+    Table,
+    Id,
+    UserId,
+    RoleId,
+}
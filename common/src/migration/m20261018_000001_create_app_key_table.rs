@@ -0,0 +1,44 @@
+use sea_orm_migration::async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AppKey::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(AppKey::Id).big_integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(AppKey::AppName).string_len(64).not_null())
+                    .col(ColumnDef::new(AppKey::KeyPrefix).string_len(16).not_null())
+                    .col(ColumnDef::new(AppKey::KeyHash).string_len(64).not_null().unique_key())
+                    .col(ColumnDef::new(AppKey::Scopes).string_len(1024).not_null().default(""))
+                    .col(ColumnDef::new(AppKey::ExpiresAt).timestamp_with_time_zone().null())
+                    .col(ColumnDef::new(AppKey::RevokedAt).timestamp_with_time_zone().null())
+                    .col(ColumnDef::new(AppKey::CreatedAt).timestamp_with_time_zone().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(AppKey::Table).if_exists().to_owned()).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AppKey {
+    Table,
+    Id,
+    AppName,
+    KeyPrefix,
+    KeyHash,
+    Scopes,
+    ExpiresAt,
+    RevokedAt,
+    CreatedAt,
+}
@@ -0,0 +1,107 @@
+use sea_orm_migration::async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+/// 权限表，并将所有权限授予`admin`角色。
+///
+/// 升级前任何已登录用户都可以调用用户接口，升级后需要对应的权限：
+/// 已有的用户需手动分配`admin`角色（`user_role`表），或通过`PUT /user/ins/{id}/roles`修改
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 接口声明的权限
+const PERMISSIONS: [(&str, &str); 2] = [("user:read", "查询用户"), ("user:write", "修改用户")];
+
+/// 拥有所有权限的角色，不存在时创建
+const ADMIN_ROLE: &str = "admin";
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Permission::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Permission::Id).big_integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(Permission::Code).string_len(128).not_null().unique_key())
+                    .col(ColumnDef::new(Permission::Description).string_len(255).null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(RolePermission::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(RolePermission::Id).big_integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(RolePermission::RoleId).big_integer().not_null())
+                    .col(ColumnDef::new(RolePermission::PermissionId).big_integer().not_null())
+                    .index(Index::create().name("uk_role_id_permission_id").col(RolePermission::RoleId).col(RolePermission::PermissionId).unique())
+                    .to_owned(),
+            )
+            .await?;
+        let mut insert = Query::insert();
+        insert.into_table(Permission::Table).columns([Permission::Code, Permission::Description]);
+        for (code, description) in PERMISSIONS {
+            insert.values_panic([code.into(), description.into()]);
+        }
+        manager.exec_stmt(insert).await?;
+        grant_admin(manager).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(RolePermission::Table).if_exists().to_owned()).await?;
+        manager.drop_table(Table::drop().table(Permission::Table).if_exists().to_owned()).await
+    }
+}
+
+/// 只执行写语句，`dry_run`时也能生成完整的SQL
+async fn grant_admin(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    let exists = Query::select().expr(Expr::val(1)).from(Role::Table).and_where(Expr::col(Role::Name).eq(ADMIN_ROLE)).to_owned();
+    let role = Query::select()
+        .expr(Expr::val(ADMIN_ROLE))
+        .from(Permission::Table)
+        .and_where(Expr::col(Permission::Code).eq(PERMISSIONS[0].0))
+        .and_where(Expr::exists(exists).not())
+        .to_owned();
+    let insert = Query::insert().into_table(Role::Table).columns([Role::Name]).select_from(role).map_err(|e| DbErr::Custom(e.to_string()))?.to_owned();
+    manager.exec_stmt(insert).await?;
+    let grants = Query::select()
+        .column((Role::Table, Role::Id))
+        .column((Permission::Table, Permission::Id))
+        .from(Role::Table)
+        .from(Permission::Table)
+        .and_where(Expr::col((Role::Table, Role::Name)).eq(ADMIN_ROLE))
+        .and_where(Expr::col((Permission::Table, Permission::Code)).is_in(PERMISSIONS.map(|(code, _)| code)))
+        .to_owned();
+    let insert = Query::insert()
+        .into_table(RolePermission::Table)
+        .columns([RolePermission::RoleId, RolePermission::PermissionId])
+        .select_from(grants)
+        .map_err(|e| DbErr::Custom(e.to_string()))?
+        .to_owned();
+    manager.exec_stmt(insert).await
+}
+
+#[derive(DeriveIden)]
+enum Role {
+    Table,
+    Id,
+    Name,
+}
+
+#[derive(DeriveIden)]
+enum Permission {
+    Table,
+    Id,
+    Code,
+    Description,
+}
+
+#[derive(DeriveIden)]
+enum RolePermission {
+    Table,
+    Id,
+    RoleId,
+    PermissionId,
+}
@@ -0,0 +1,59 @@
+use sea_orm::DbBackend;
+use sea_orm_migration::async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 用户、角色的主键改为自增（MySQL上早期版本创建的表），用户名、邮箱唯一
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::MySql {
+            manager.alter_table(auto_increment_id(User::Table)).await?;
+            manager.alter_table(auto_increment_id(Role::Table)).await?;
+            manager.alter_table(auto_increment_id(UserRole::Table)).await?;
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(UserRole::Table)
+                        .modify_column(ColumnDef::new(UserRole::UserId).big_integer().not_null())
+                        .modify_column(ColumnDef::new(UserRole::RoleId).big_integer().not_null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        manager.create_index(Index::create().name("uk_username").table(User::Table).col(User::Username).unique().to_owned()).await?;
+        manager.create_index(Index::create().name("uk_email").table(User::Table).col(User::Email).unique().to_owned()).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_index(Index::drop().name("uk_email").table(User::Table).to_owned()).await?;
+        manager.drop_index(Index::drop().name("uk_username").table(User::Table).to_owned()).await
+    }
+}
+
+fn auto_increment_id(table: impl IntoIden + 'static) -> TableAlterStatement {
+    let id = ColumnDef::new(User::Id).big_integer().not_null().auto_increment().to_owned();
+    Table::alter().table(table).modify_column(id).to_owned()
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+    Username,
+    Email,
+}
+
+#[derive(DeriveIden)]
+enum Role {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum UserRole {
+    Table,
+    UserId,
+    RoleId,
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 用户登录失败次数、锁定截止时间及最后修改密码的时间，SQLite每次只能添加一列
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let columns = [
+            ColumnDef::new(User::FailedLogins).integer().not_null().default(0).to_owned(),
+            ColumnDef::new(User::LockedUntil).timestamp_with_time_zone().null().to_owned(),
+            ColumnDef::new(User::PasswordChangedAt).timestamp_with_time_zone().null().to_owned(),
+        ];
+        for mut column in columns {
+            manager.alter_table(Table::alter().table(User::Table).add_column(&mut column).to_owned()).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [User::FailedLogins, User::LockedUntil, User::PasswordChangedAt] {
+            manager.alter_table(Table::alter().table(User::Table).drop_column(column).to_owned()).await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    FailedLogins,
+    LockedUntil,
+    PasswordChangedAt,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::sqlx::types::chrono::Utc;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 邮箱验证、重置密码的一次性令牌，只保存令牌的摘要。
+///
+/// 注意：迁移时已有的所有用户都标记为已验证邮箱（`email_verified_at`为迁移时间），
+/// 否则升级后已有用户都无法登录；这些用户的邮箱没有经过验证，需要时请在迁移后自行清空
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserToken::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(UserToken::Id).big_integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(UserToken::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(UserToken::Purpose).string_len(32).not_null())
+                    .col(ColumnDef::new(UserToken::TokenHash).string_len(64).not_null().unique_key())
+                    .col(ColumnDef::new(UserToken::ExpiresAt).timestamp_with_time_zone().not_null())
+                    .col(ColumnDef::new(UserToken::UsedAt).timestamp_with_time_zone().null())
+                    .col(ColumnDef::new(UserToken::CreatedAt).timestamp_with_time_zone().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(Index::create().name("idx_user_id_purpose").table(UserToken::Table).col(UserToken::UserId).col(UserToken::Purpose).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(ColumnDef::new(User::EmailVerifiedAt).timestamp_with_time_zone().null())
+                    .to_owned(),
+            )
+            .await?;
+        // 已有用户视为已验证，见类型上的说明
+        let verified = Query::update().table(User::Table).value(User::EmailVerifiedAt, Utc::now()).to_owned();
+        manager.exec_stmt(verified).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(Table::alter().table(User::Table).drop_column(User::EmailVerifiedAt).to_owned()).await?;
+        manager.drop_table(Table::drop().table(UserToken::Table).if_exists().to_owned()).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    EmailVerifiedAt,
+}
+
+#[derive(DeriveIden)]
+enum UserToken {
+    Table,
+    Id,
+    UserId,
+    Purpose,
+    TokenHash,
+    ExpiresAt,
+    UsedAt,
+    CreatedAt,
+}
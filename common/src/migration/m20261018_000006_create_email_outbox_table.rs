@@ -0,0 +1,61 @@
+use sea_orm_migration::async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 待发送的邮件，发送成功后删除，多次失败后保留为`dead`，包含一次性令牌的邮件同时清空正文
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(EmailOutbox::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(EmailOutbox::Id).big_integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(EmailOutbox::Recipient).string_len(255).not_null())
+                    .col(ColumnDef::new(EmailOutbox::Subject).string_len(255).not_null())
+                    .col(ColumnDef::new(EmailOutbox::TextBody).text().not_null())
+                    .col(ColumnDef::new(EmailOutbox::HtmlBody).text().null())
+                    .col(ColumnDef::new(EmailOutbox::Sensitive).boolean().not_null().default(false))
+                    .col(ColumnDef::new(EmailOutbox::Status).string_len(16).not_null())
+                    .col(ColumnDef::new(EmailOutbox::Attempts).integer().not_null().default(0))
+                    .col(ColumnDef::new(EmailOutbox::NextAttemptAt).timestamp_with_time_zone().not_null())
+                    .col(ColumnDef::new(EmailOutbox::LastError).text().null())
+                    .col(ColumnDef::new(EmailOutbox::CreatedAt).timestamp_with_time_zone().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_status_next_attempt_at")
+                    .table(EmailOutbox::Table)
+                    .col(EmailOutbox::Status)
+                    .col(EmailOutbox::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(EmailOutbox::Table).if_exists().to_owned()).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum EmailOutbox {
+    Table,
+    Id,
+    Recipient,
+    Subject,
+    TextBody,
+    HtmlBody,
+    Sensitive,
+    Status,
+    Attempts,
+    NextAttemptAt,
+    LastError,
+    CreatedAt,
+}
@@ -1,6 +1,6 @@
 use crate::queue::errors::{Error, Result};
 use std::any::type_name;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::broadcast;
 use tokio::sync::broadcast::{Receiver, Sender};
@@ -8,6 +8,29 @@ use tokio::sync::broadcast::{Receiver, Sender};
 pub struct TokioSender<M: prost::Message + Clone> {
     tx: Sender<M>,
     stop: Arc<AtomicBool>,
+    stats: Arc<QueueStats>,
+}
+
+/// 队列统计：发送成功、接收方落后丢失及发送失败的消息数量
+#[derive(Debug, Default)]
+pub struct QueueStats {
+    sent: AtomicU64,
+    lagged: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl QueueStats {
+    pub fn sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    pub fn lagged(&self) -> u64 {
+        self.lagged.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
 }
 
 impl<M: prost::Message + Clone> TokioSender<M> {
@@ -16,6 +39,7 @@ impl<M: prost::Message + Clone> TokioSender<M> {
         Self {
             tx,
             stop: Default::default(),
+            stats: Default::default(),
         }
     }
 
@@ -23,15 +47,23 @@ impl<M: prost::Message + Clone> TokioSender<M> {
     pub fn send(&self, event: M) -> Result<()> {
         if self.stop.load(Ordering::Relaxed) {
             tracing::warn!("cluster event sender has been stopped fail.");
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
             Err(Error::Closed)
         } else if let Err(e) = self.tx.send(event) {
             tracing::warn!("QueueSender send fail, {}", e);
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
             Err(Error::Send)
         } else {
+            self.stats.sent.fetch_add(1, Ordering::Relaxed);
             Ok(())
         }
     }
 
+    #[inline]
+    pub fn stats(&self) -> &QueueStats {
+        &self.stats
+    }
+
     #[inline]
     pub fn stop(&self) {
         self.stop.store(true, Ordering::Relaxed);
@@ -41,11 +73,17 @@ impl<M: prost::Message + Clone> TokioSender<M> {
     pub fn is_empty(&self) -> bool {
         self.tx.is_empty()
     }
+
+    #[inline]
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
 }
 
 pub struct TokioReceiver<M: prost::Message + Clone> {
     rx: Receiver<M>,
     selector: fn(M) -> Option<M>,
+    stats: Arc<QueueStats>,
 }
 
 impl<M: prost::Message + Clone> TokioReceiver<M> {
@@ -53,6 +91,7 @@ impl<M: prost::Message + Clone> TokioReceiver<M> {
         Self {
             rx: sender.tx.subscribe(),
             selector,
+            stats: sender.stats.clone(),
         }
     }
 
@@ -63,6 +102,7 @@ impl<M: prost::Message + Clone> TokioReceiver<M> {
             Err(e) => {
                 if let broadcast::error::RecvError::Lagged(lag) = e {
                     tracing::warn!("TokioSubscriber[{}] is lagged: {}", type_name::<M>(), lag);
+                    self.stats.lagged.fetch_add(lag, Ordering::Relaxed);
                     Ok(None)
                 } else {
                     Err(Error::Closed)
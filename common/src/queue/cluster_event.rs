@@ -1,4 +1,4 @@
-use crate::queue::broadcast::{TokioReceiver, TokioSender};
+use crate::queue::broadcast::{QueueStats, TokioReceiver, TokioSender};
 use crate::queue::errors::Result;
 use crate::queue::message::event::ClusterEventProto;
 
@@ -38,6 +38,20 @@ impl ClusterEventSender {
             ClusterEventSender::Queue(sender) => sender.is_empty(),
         }
     }
+
+    #[inline]
+    pub fn is_stopped(&self) -> bool {
+        match self {
+            ClusterEventSender::Queue(sender) => sender.is_stopped(),
+        }
+    }
+
+    #[inline]
+    pub fn stats(&self) -> &QueueStats {
+        match self {
+            ClusterEventSender::Queue(sender) => sender.stats(),
+        }
+    }
 }
 
 pub enum ClusterEventReceiver {
@@ -1,6 +1,6 @@
 // This file is @generated by prost-build.
 /// 全局集群服务事件
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ClusterEventProto {
     #[prost(int64, tag = "1")]
     pub ts: i64,
@@ -9,7 +9,7 @@ pub struct ClusterEventProto {
 }
 /// Nested message and enum types in `ClusterEventProto`.
 pub mod cluster_event_proto {
-    #[derive(Clone, Copy, PartialEq, ::prost::Oneof)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum ClusterEvent {
         #[prost(message, tag = "2")]
         UserEvent(super::UserEvent),
@@ -18,8 +18,54 @@ pub mod cluster_event_proto {
     }
 }
 /// //////////////////////////////// 用户事件 //////////////////////////////////////////
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
-pub struct UserEvent {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserEvent {
+    #[prost(enumeration = "UserEventType", tag = "1")]
+    pub event_type: i32,
+    #[prost(int64, tag = "2")]
+    pub id: i64,
+    #[prost(string, tag = "3")]
+    pub username: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub email: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub phone: ::prost::alloc::string::String,
+    /// 变更后的角色
+    #[prost(int64, repeated, tag = "6")]
+    pub role_ids: ::prost::alloc::vec::Vec<i64>,
+}
 /// //////////////////////////////// 组织事件 //////////////////////////////////////////
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct GroupEvent {}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum UserEventType {
+    Unknown = 0,
+    Created = 1,
+    Updated = 2,
+    Deleted = 3,
+}
+impl UserEventType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unknown => "UNKNOWN",
+            Self::Created => "CREATED",
+            Self::Updated => "UPDATED",
+            Self::Deleted => "DELETED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "UNKNOWN" => Some(Self::Unknown),
+            "CREATED" => Some(Self::Created),
+            "UPDATED" => Some(Self::Updated),
+            "DELETED" => Some(Self::Deleted),
+            _ => None,
+        }
+    }
+}
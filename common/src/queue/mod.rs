@@ -34,7 +34,7 @@ mod tests {
             sender
                 .send(ClusterEventProto {
                     ts: i,
-                    cluster_event: Some(ClusterEvent::UserEvent(UserEvent {})),
+                    cluster_event: Some(ClusterEvent::UserEvent(UserEvent::default())),
                 })
                 .expect("TODO: panic message");
         }
@@ -43,4 +43,26 @@ mod tests {
             tokio::time::sleep(Duration::from_millis(50)).await;
         }
     }
+
+    #[tokio::test]
+    pub async fn test_stats() {
+        let event = |ts| ClusterEventProto {
+            ts,
+            cluster_event: Some(ClusterEvent::UserEvent(UserEvent::default())),
+        };
+        let sender = ClusterEventSender::Queue(TokioSender::new(2));
+        // 没有接收方时发送失败
+        assert!(sender.send(event(0)).is_err());
+        let mut subscriber = sender.subscribe().unwrap();
+        for i in 1..=3 {
+            sender.send(event(i)).unwrap();
+        }
+        assert!(subscriber.recv_mut().await.unwrap().is_none());
+        assert_eq!(subscriber.recv_mut().await.unwrap().unwrap().ts, 2);
+        sender.stop();
+        assert!(sender.send(event(4)).is_err());
+
+        let stats = sender.stats();
+        assert_eq!((stats.sent(), stats.lagged(), stats.dropped()), (3, 1, 2));
+    }
 }